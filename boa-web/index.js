@@ -5,13 +5,27 @@ const FIELD_WIDTH = 8;     // number of fields in a single row
 const PLAYABLE_PLAYER = 1; // how many human players are there? Currenty, only PLAYABLE_PLAYER = 1 is supported.
 
 let NUMBER_PLAYOUTS = 2000; // how many random playouts will be done from every field by AI.
+let THINKING_TIME_IN_MILLIS = 0; // maximal time the AI may think, 0 means no time limit.
 
 let ai = null;          // ai object from the WASM code
 let currstate = null;   // current game state object
-let wasmobj = null;     // WASM object containingi the memory
+let session = null;     // game session with the history of all moves (for undo/redo)
+let ai_is_thinking = false; // true while the AI evaluates its next move
+let search = null;      // search of the AI, continued after every move and while the player thinks
+
+const PONDER_MILLIS_PER_STEP = 20; // the AI thinks in small steps during the turn of the player, so the page stays responsive
+const MAX_PONDER_MILLIS = 10000;   // the AI stops thinking after this time if the player does not move
 
 let bean_img = new Image(); // preloaded bean image 
 
+const STORAGE_KEY = "boa_game_state"; // key of the current game state in the localStorage
+
+const OPENING_BOOK_URL = "book.bin"; // optional opening book generated by `boabin book`, placed next to index.html
+let opening_book_bytes = null;       // the loaded opening book, set again for every new AI object
+
+const MILLIS_PER_STONE = 150; // duration of the animation of a single sown stone
+let animation_timer = null;   // timer of the running animation of a move
+
 
 initialize_wasm_object();
 
@@ -25,14 +39,96 @@ add_event_listener_to_slider();
 debug_output() 
 
 /**
- * Initialize WASM code and creates the object which also holds the WASM memory.
+ * Initialize WASM code and creates the AI and the initial game state.
  */
 async function initialize_wasm_object() {
-    wasmobj = await init();
+    await init();
 
     wasm.init_panic_hook();   
     ai = new wasm.AI();
-    currstate = new wasm.GameState();
+    session = new wasm.GameSession(initial_state_from_url() || stored_state() || new wasm.GameState(new wasm.RuleSet(FIELD_WIDTH)));
+    currstate = session.current_state();
+    update_field_representation(currstate);
+    store_state(currstate);
+    load_opening_book();
+}
+
+/**
+ * Loads the opening book if the server provides one. Without a book, the AI searches every position.
+ */
+async function load_opening_book() {
+    try {
+        const response = await fetch(OPENING_BOOK_URL);
+        if (response.ok) {
+            opening_book_bytes = new Uint8Array(await response.arrayBuffer());
+            use_opening_book();
+        }
+    } catch (error) {
+        console.log("No opening book: " + error);
+    }
+}
+
+/**
+ * Lets the current AI object play the moves of the loaded opening book.
+ */
+function use_opening_book() {
+    if (opening_book_bytes == null || ai == null) {
+        return;
+    }
+    try {
+        ai.set_opening_book_wasm(wasm.OpeningBook.from_bytes_wasm(opening_book_bytes));
+    } catch (error) {
+        console.log("Error while loading the opening book: " + error);
+    }
+}
+
+/**
+ * Initial game state given by the URL parameter 'position' in position notation (e.g. ?position=2222222222222222/2222222222222222%200%20L8),
+ * null if there is no (valid) position.
+ */
+function initial_state_from_url() {
+    var notation = new URLSearchParams(window.location.search).get('position');
+    if (notation != null) {
+        try {
+            var state = wasm.GameState.from_notation_wasm(notation);
+            if (state.rules.length_of_field == FIELD_WIDTH) {
+                return state;
+            }
+            console.log("Position '" + notation + "' does not fit to a field with " + FIELD_WIDTH + " cells per row.");
+        } catch (error) {
+            console.log(error);
+        }
+    }
+    return null;
+}
+
+/**
+ * Game state stored in the localStorage by the last visit of the page, null if there is no (valid) state.
+ */
+function stored_state() {
+    var stored = window.localStorage.getItem(STORAGE_KEY);
+    if (stored != null) {
+        try {
+            var state = wasm.GameState.from_js(JSON.parse(stored));
+            if (state.rules.length_of_field == FIELD_WIDTH && state.curr_player == 0) {
+                return state;
+            }
+        } catch (error) {
+            console.log("Ignoring stored game state: " + error);
+        }
+    }
+    return null;
+}
+
+/**
+ * Stores the game state in the localStorage such that the game can be continued after reloading the page.
+ */
+function store_state(gamestate) {
+    try {
+        window.localStorage.setItem(STORAGE_KEY, JSON.stringify(gamestate.to_js()));
+    } catch (error) {
+        console.log("Could not store game state: " + error);
+    }
 }
 
 /**
@@ -48,17 +144,57 @@ function preload_images() {
 }
 
 /**
- * Adds event listener to the slides which updates number of playouts of the AI.
+ * Adds event listener to the slides which update the number of playouts and the time to think of the AI.
  */
 function add_event_listener_to_slider() {
     var slider = document.getElementById("playout_range");
     var information_text = document.getElementById("info_text_playout_number");
+    var difficulty_select = document.getElementById("difficulty_select");
     slider.value = NUMBER_PLAYOUTS;
     information_text.innerHTML =  "Number playouts to think: " + NUMBER_PLAYOUTS;
     slider.oninput = function() {
         NUMBER_PLAYOUTS = this.value;
         information_text.innerHTML =  "Number playouts to think: " + NUMBER_PLAYOUTS;
     }
+
+    // A difficulty level sets the number of playouts and lets the AI make mistakes on purpose. "Custom" uses the
+    // strongest move with the playouts of the slider.
+    difficulty_select.onchange = function() {
+        if (ai == null || ai_is_thinking) {
+            return;
+        }
+        ai.free();
+        ai = new wasm.AI();
+        use_opening_book();
+        if (search != null) {
+            search.free();
+            search = null;
+        }
+        if (this.value != "Custom") {
+            ai.set_difficulty(wasm.Difficulty[this.value]);
+            const budget = ai.budget();
+            NUMBER_PLAYOUTS = budget.max_playouts();
+            budget.free();
+            slider.value = NUMBER_PLAYOUTS;
+            information_text.innerHTML =  "Number playouts to think: " + NUMBER_PLAYOUTS;
+        }
+    }
+
+    var time_slider = document.getElementById("thinking_time_range");
+    var time_information_text = document.getElementById("info_text_thinking_time");
+    var show_thinking_time = function() {
+        if (THINKING_TIME_IN_MILLIS == 0) {
+            time_information_text.innerHTML = "Seconds to think: no limit";
+        } else {
+            time_information_text.innerHTML = "Seconds to think: at most " + (THINKING_TIME_IN_MILLIS / 1000);
+        }
+    }
+    time_slider.value = THINKING_TIME_IN_MILLIS / 1000;
+    show_thinking_time();
+    time_slider.oninput = function() {
+        THINKING_TIME_IN_MILLIS = parseInt(this.value * 1000);
+        show_thinking_time();
+    }
 }
 
 /**
@@ -84,6 +220,10 @@ function add_event_listener_to_buttons() {
     // document.querySelector('#nextmove').addEventListener('click', next_move);
 
     document.querySelector('#new_game_button').addEventListener('click', start_new_game);
+    document.querySelector('#undo_button').addEventListener('click', undo_move);
+    document.querySelector('#redo_button').addEventListener('click', redo_move);
+    document.querySelector('#save_record_button').addEventListener('click', save_record);
+    document.querySelector('#load_record_input').addEventListener('change', load_record);
     document.querySelector('#enable_debug_output').addEventListener('change', debug_output);
 }
 
@@ -91,8 +231,88 @@ function add_event_listener_to_buttons() {
  * Start new game.
  */
 function start_new_game() {    
-    currstate = new wasm.GameState();
+    if (ai_is_thinking) {
+        return;
+    }
+    var layout = document.querySelector('#layout_select').value;
+    session = new wasm.GameSession(wasm.GameState.from_layout(new wasm.RuleSet(FIELD_WIDTH), layout));
+    currstate = session.current_state();
+    update_field_representation(currstate);
+    store_state(currstate);
+}
+
+/**
+ * Takes back moves until it's again the turn of the human player (i.e., the own move and the answer of the AI).
+ */
+function undo_move() {
+    if (session == null || ai_is_thinking || !session.can_undo()) {
+        return;
+    }
+    do {
+        session.undo();
+    } while (session.can_undo() && session.current_state().curr_player != 0);
+    currstate = session.current_state();
     update_field_representation(currstate);
+    store_state(currstate);
+}
+
+/**
+ * Replays undone moves until it's again the turn of the human player.
+ */
+function redo_move() {
+    if (session == null || ai_is_thinking || !session.can_redo()) {
+        return;
+    }
+    do {
+        session.redo();
+    } while (session.can_redo() && session.current_state().curr_player != 0);
+    currstate = session.current_state();
+    update_field_representation(currstate);
+    store_state(currstate);
+}
+
+/**
+ * Downloads the record of the game played so far (human player against the AI).
+ */
+function save_record() {
+    if (session == null) {
+        return;
+    }
+    var now = new Date();
+    var date = now.getFullYear() + "." + String(now.getMonth() + 1).padStart(2, "0") + "." + String(now.getDate()).padStart(2, "0");
+    var record = session.to_record("Human", "AI", date);
+
+    var link = document.createElement('a');
+    link.href = URL.createObjectURL(new Blob([record], {type: 'text/plain'}));
+    link.download = "boa-" + date + ".txt";
+    link.click();
+    URL.revokeObjectURL(link.href);
+}
+
+/**
+ * Loads a game record chosen by the user. All moves are replayed and can be reviewed via undo and redo.
+ */
+function load_record(event) {
+    var file = event.target.files[0];
+    if (file == null || ai_is_thinking) {
+        return;
+    }
+    file.text().then(function (text) {
+        try {
+            var loaded_session = wasm.GameSession.from_record(text);
+            if (loaded_session.current_state().rules.length_of_field != FIELD_WIDTH) {
+                console.log("The game was not played on a field with " + FIELD_WIDTH + " cells per row.");
+                return;
+            }
+            session = loaded_session;
+            currstate = session.current_state();
+            update_field_representation(currstate);
+            store_state(currstate);
+        } catch (error) {
+            console.log(error);
+        }
+    });
+    event.target.value = "";
 }
 
 /**
@@ -145,10 +365,73 @@ function get_color(num_stones) {
  * @param {*} gamestate Gamestate that should be shown on the UI.
  */
 function update_field_representation(gamestate) {
+    stop_animation();
     const pre = document.getElementById("boa-viz");
     console.log("Updating game field");
-    pre.textContent = gamestate.render();
+    pre.textContent = gamestate.render() + "\nPosition: " + gamestate.to_notation();
+
+    draw_field(function (player, position) {
+        return gamestate.get_number_stones_at(player, position);
+    });
+}
 
+/**
+ * Shows the move from position in state_before stone by stone (using the trace of the move) and finally state_after.
+ */
+function animate_move(state_before, position, state_after) {
+    stop_animation();
+    var cells = [[], []];
+    for (let player = 0; player < 2; player++) {
+        for (let cell = 0; cell < 2*FIELD_WIDTH; cell++) {
+            cells[player].push(state_before.get_number_stones_at(player, cell));
+        }
+    }
+    var trace = state_before.trace_move(position);
+    var player = trace.player;
+    var drops = trace.drops;
+    trace.free();
+
+    var stones_in_the_hand = cells[player][position];
+    cells[player][position] = 0;
+    var step = 0;
+    var get_stones = function (player, cell) {
+        return cells[player][cell];
+    };
+    var next_step = function () {
+        if (step == drops.length) {
+            update_field_representation(state_after); // removes captured stones
+            return;
+        }
+        if (stones_in_the_hand == 0) { // relay sowing: pick up the stones of the last cell
+            stones_in_the_hand = cells[player][drops[step - 1]];
+            cells[player][drops[step - 1]] = 0;
+        }
+        cells[player][drops[step]] += 1;
+        stones_in_the_hand -= 1;
+        step += 1;
+        draw_field(get_stones);
+        animation_timer = setTimeout(next_step, MILLIS_PER_STONE);
+    };
+    draw_field(get_stones);
+    animation_timer = setTimeout(next_step, MILLIS_PER_STONE);
+}
+
+/**
+ * Stops the running animation of a move (if any).
+ */
+function stop_animation() {
+    if (animation_timer != null) {
+        clearTimeout(animation_timer);
+        animation_timer = null;
+    }
+}
+
+/**
+ * Draws the beans on the board and updates the buttons in the debug output.
+ *
+ * @param {*} get_stones Function returning the number of stones of a player at a position.
+ */
+function draw_field(get_stones) {
     var c = document.getElementById("myCanvas");
     var img = document.getElementById("boardimg");
     c.style.position = "absolute";
@@ -165,7 +448,7 @@ function update_field_representation(gamestate) {
                 // Update information in the buttongs
                 var elemid = '#p' + (player+1) + '_pos' + position;
                 var elem = document.querySelector(elemid);
-                var stones = gamestate.get_number_stones_at(player, position);
+                var stones = get_stones(player, position);
                 //console.log("Received: " + " --> " + stones)
                 elem.textContent = stones;
                 elem.style.background=get_color(stones)
@@ -213,6 +496,25 @@ function update_field_representation(gamestate) {
     }
 }
 
+/**
+ * Show an alert if the game of the session is over (won by one side or drawn).
+ * @returns true if the game is over.
+ */
+function announce_outcome() {
+    const outcome = session.outcome();
+    if (outcome === undefined) {
+        return false;
+    }
+    if (outcome == wasm.Outcome.Draw) {
+        alert("Game over! It's a draw.");
+    } else if (outcome == wasm.Outcome.Player0Wins) {
+        alert("Game over! Congratulations, you won!");
+    } else {
+        alert("Game over! Winner is Computer!");
+    }
+    return true;
+}
+
 /**
  * Handle what happens when an area map or a button in the debug output is clicked (basically, to the move...). In more details, two things happen.
  * First, if it's player turn, clicking on a field will make a move for the player. Afterwards, the evaluation for the best move of AI is triggered.
@@ -221,7 +523,7 @@ function update_field_representation(gamestate) {
  */
 function click_on_cell(event) {
     // console.log("Currstate: " + currstate + ", ai: " + ai + "((currstate != null) && (ai != null)) " + ((currstate != null) && (ai != null)));
-    if ((currstate != null) && (ai != null)) {
+    if ((currstate != null) && (ai != null) && !ai_is_thinking) {
         var current_player = currstate.curr_player;
         // console.log("Clicked on button: event " + event);
         if (current_player == 0) {            
@@ -230,110 +532,127 @@ function click_on_cell(event) {
             try {
                 // -------------------
                 // Here, we make the move of the player.
-                let newstate = currstate.make_move_wasm(position);
-                update_field_representation(newstate);
-                if (newstate.game_over()) {
-                    alert("Game over! Congratulations, you won!");
-                }
+                let newstate = session.make_move_wasm(position);
+                animate_move(currstate, position, newstate);
+                const game_is_over = announce_outcome();
                 currstate = newstate;                
                 // console.log(newstate);
 
                 // ---------------------
                 // Here, the block comes, where AI evaluates a move and the actual move is done.
-                if (currstate != null && !currstate.game_over()) {                    
+                if (currstate != null && !game_is_over) {                    
+                    ai_is_thinking = true;
                     try {
                         // Now, AI makes a move
                         // console.log("Before doing AI step, the state is: " + currstate.render());                            
                         // To show the progress, we do not calculate all steps at once but by small batches. This allows us to update the progress bar inbetween.
-                        // And due to the current implemententation which does not do a real Monte Carlo search, it is ok like this.
+                        // The search is started once and continued by every batch, so nothing is calculated twice.
                         var num_batches =  100; // 1 batch per 1% of progress
                         var playouts_per_batch = parseInt(NUMBER_PLAYOUTS / num_batches); 
                         if (playouts_per_batch == 0) { // user selected less than 100 playouts
                             num_batches = NUMBER_PLAYOUTS;
                             playouts_per_batch = 1;
                         }
+                        // With a time limit, every batch gets the same share of the time.
+                        var millis_per_batch = Math.max(1, parseInt(THINKING_TIME_IN_MILLIS / num_batches));
 
                         const ai_progress = document.getElementById("ai_progress");
                         const chances_to_win_elem = document.getElementById("chances_to_win");
                         ai_progress.value = 0;
-                        var diff_wins_distribution = null;
+
+                        // Moves of the opening book are played without thinking
+                        const book_move = ai.book_move(currstate);
+                        if (book_move !== undefined) {
+                            let new_state_after_ai_move = session.make_move_wasm(book_move);
+                            ai_is_thinking = false;
+                            animate_move(currstate, book_move, new_state_after_ai_move);
+                            announce_outcome();
+                            currstate = new_state_after_ai_move;
+                            store_state(currstate);
+                            ai_progress.value = 100;
+                            chances_to_win_elem.innerHTML = "Move from the opening book";
+                            return;
+                        }
+
+                        const search = search_for_current_state();
 
                         // Crazy way from stack-overflow how to update the progress bar during calculations.
                         // Web pogramming is sooo weird...
                         var loop = function (batch, played_playouts) {
-                            // console.log("Batch: " + batch + " played_playouts: " + played_playouts + " playouts_per_batch: " + playouts_per_batch + " diff_wins_distribution: " + diff_wins_distribution, " num_batches: " + num_batches);
-                            if (batch == num_batches - 1) {
-                                playouts_per_batch = NUMBER_PLAYOUTS - played_playouts;
-                            }
-                            let tmp = ai.evaluate_state_for_next_move(currstate, playouts_per_batch);
-                            let tmp_diffs = new Int32Array(wasmobj.memory.buffer, tmp, 2*FIELD_WIDTH);
-                            if (diff_wins_distribution == null) {
-                                diff_wins_distribution = tmp_diffs.slice();
-                            } else {
-                                for (let index = 0; index < tmp_diffs.length; index++) {
-                                    const element = tmp_diffs[index];
-                                    diff_wins_distribution[index] += element;                                        
+                            try {
+                                if (batch == num_batches - 1) {
+                                    playouts_per_batch = NUMBER_PLAYOUTS - played_playouts;
                                 }
-                            }
-                            
-                            played_playouts += playouts_per_batch;                                
-
-                            // update progress bar
-                            // --------------
-                            ai_progress.value = parseInt(played_playouts / NUMBER_PLAYOUTS * 100); 
-                            ai_progress.setAttribute('data-content', 'Played ' + ai_progress.value + ' playouts'); 
-                            // --------------
-
-
-                            if (batch < num_batches) {
-                                setTimeout(function () {
-                                    loop(batch + 1, played_playouts)
-                                }, 1); 
-                            } else {
-                                // Tada! AI is ready with evaluations and we can start checking for the best move.
-                                // What AI provides is an array of nunbers - one number per field. This number states how often
-                                // AI would win from this location if both - AI and player - would play random games starting from this
-                                // location until someone wins. Negative value N means that the player would win N times more then the AI.
-                                // Positive number P means that AI would win P times more then the human player.
-                                // Best position is therefore the one with hightest P value.
-                                // 
-                                // This strategy may seems weird but it works really well in this simple game.
-                                console.log("After doing AI step, the winner diff is: " + diff_wins_distribution);
-                                let max_value = -NUMBER_PLAYOUTS-1;
-                                let max_index = 0;
-                                for (let index = 0; index < diff_wins_distribution.length; index++) {
-                                    const value_at_index = diff_wins_distribution[index];
-                                    if ((value_at_index > max_value) && (newstate.get_number_stones_at(1, index) > 0)) {
-                                        max_value = value_at_index;
-                                        max_index = index;
-                                    }
+                                if (THINKING_TIME_IN_MILLIS > 0) {
+                                    search.step_for(millis_per_batch);
+                                } else {
+                                    search.step(playouts_per_batch);
                                 }
-                                console.log("Choose best position: " + max_index);
-                                let new_state_after_ai_move = newstate.make_move_wasm(max_index);                    
-                                update_field_representation(new_state_after_ai_move);
-                                if (new_state_after_ai_move.game_over()) {
-                                    alert("Game over! Winner is Computer!");
-                                }
-                                currstate = new_state_after_ai_move;
-                                console.log("After doing AI step, the state is: " + new_state_after_ai_move.render());
-
-                                var win_chances_ai = 0;
-                                var win_chances_player = 0;
-                                for (let index = 0; index < diff_wins_distribution.length; index++) {
-                                    const v = diff_wins_distribution[index];
-                                    if (v < 0) {
-                                        win_chances_player += diff_wins_distribution[index];
-                                    } else {
-                                        win_chances_ai += diff_wins_distribution[index];
+                                played_playouts += playouts_per_batch;                                
+
+                                // update progress bar
+                                // --------------
+                                ai_progress.value = parseInt(played_playouts / NUMBER_PLAYOUTS * 100); 
+                                ai_progress.setAttribute('data-content', 'Played ' + ai_progress.value + ' playouts'); 
+                                // --------------
+
+
+                                if (batch < num_batches - 1 && !search.is_finished()) {
+                                    setTimeout(function () {
+                                        loop(batch + 1, played_playouts)
+                                    }, 1); 
+                                } else {
+                                    // Tada! AI is ready with evaluations and chooses its move (not always the best one at lower difficulty levels).
+                                    // The scores are given per field for the AI: for the Monte Carlo searches, a negative value N means
+                                    // that the player won N times more than the AI in the playouts after a move from this field,
+                                    // a positive value P means that the AI won P times more.
+                                    const evaluation = search.evaluation();
+                                    const scores = evaluation.scores;
+                                    const max_index = ai.select_move(evaluation);
+                                    evaluation.free();
+                                    if (max_index === undefined) {
+                                        ai_is_thinking = false;
+                                        console.log("The AI found no move");
+                                        return;
                                     }
+                                    console.log("After doing AI step, the scores are: " + scores);
+                                    console.log("Choose best position: " + max_index);
+                                    let new_state_after_ai_move = session.make_move_wasm(max_index);                    
+                                    ai_is_thinking = false;
+                                    animate_move(currstate, max_index, new_state_after_ai_move);
+                                    announce_outcome();
+                                    currstate = new_state_after_ai_move;
+                                    store_state(currstate);
+                                    console.log("After doing AI step, the state is: " + new_state_after_ai_move.render());
+
+                                    var win_chances_ai = 0;
+                                    var win_chances_player = 0;
+                                    for (let index = 0; index < scores.length; index++) {
+                                        const v = scores[index];
+                                        if (v < 0) {
+                                            win_chances_player += v;
+                                        } else {
+                                            win_chances_ai += v;
+                                        }
 
+                                    }
+                                    // Static evaluation of the position for the computer (player 1), positive values are good for the computer
+                                    let position_value = new_state_after_ai_move.evaluate(wasm.EvaluatorKind.Balanced, 1);
+                                    chances_to_win_elem.innerHTML = "Chances to win: (Computer) " + win_chances_ai + " vs " + (-win_chances_player) + " (Player)"
+                                        + ", position value for Computer: " + position_value.toFixed(1);
+
+                                    // Think ahead while the player considers the next move
+                                    ponder(Date.now() + MAX_PONDER_MILLIS);
                                 }
-                                chances_to_win_elem.innerHTML = "Chances to win: (Computer) " + win_chances_ai + " vs " + (-win_chances_player) + " (Player)" 
+                            } catch (error) {
+                                ai_is_thinking = false;
+                                console.log("Error while making AI move: " + error);
                             }
                         }
                                             
                         loop(0, 0);
                     } catch (error) {
+                        ai_is_thinking = false;
                         console.log("Error while making AI move: " + error);
                     }
                     
@@ -346,6 +665,44 @@ function click_on_cell(event) {
     }
 }
 
+/**
+ * The search of the AI continued in the current state. A new search is started if the current state cannot be reached
+ * from the position of the last search (e.g. after a new game or an undo).
+ */
+function search_for_current_state() {
+    if (search == null || !search.advance_to(currstate)) {
+        if (search != null) {
+            search.free();
+        }
+        search = ai.start_search(currstate);
+    }
+    return search;
+}
+
+/**
+ * Let the AI think in small steps during the turn of the player until the player moves or the time is over. The next
+ * move of the AI continues this search.
+ *
+ * @param {*} end_time Time (as given by Date.now()) when the AI stops thinking.
+ */
+function ponder(end_time) {
+    if (ai_is_thinking || currstate == null || currstate.curr_player !== 0 || session.outcome() != null || Date.now() >= end_time) {
+        return;
+    }
+    try {
+        const search = search_for_current_state();
+        if (search.is_finished()) {
+            return;
+        }
+        search.step_for(PONDER_MILLIS_PER_STEP);
+        setTimeout(function () {
+            ponder(end_time);
+        }, 10);
+    } catch (error) {
+        console.log("Error while pondering: " + error);
+    }
+}
+
 /**
  * On mouse-over over own part of the game field, we show the distribution of beans if one does a move from this location.
  * 
@@ -353,8 +710,11 @@ function click_on_cell(event) {
  */
 function mouse_over_cell(event) {
     if (currstate != null && currstate.curr_player === 0) {
-        var position = extract_button_position_from_element_id(event.target.id);
+        var position = parseInt(extract_button_position_from_element_id(event.target.id));
         console.log("Mouse over pos=" + position);
+        if (!currstate.is_legal_move(position)) {
+            return;
+        }
         try {
             let newstate = currstate.make_move_wasm(position);
             update_field_representation(newstate);
@@ -0,0 +1,352 @@
+use crate::ai::budget::SearchBudget;
+use crate::ai::evaluation::Evaluation;
+use crate::ai::evaluator::{Evaluator, StoneDifference};
+use crate::{GameError, GameState, Outcome};
+use instant::Instant;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Default depth (in plies) up to which the iterative deepening searches.
+pub const DEFAULT_MAX_DEPTH: u32 = 8;
+
+/// Score of a won position. Wins after fewer plies get higher scores (`WIN_SCORE - plies`), so
+/// the search prefers the fastest win and the slowest loss.
+pub const WIN_SCORE: i32 = 1_000_000;
+
+/// Scores above this value (or below its negation) are wins (or losses) within the search.
+const MIN_WIN_SCORE: i32 = WIN_SCORE - 10_000;
+
+const INFINITY: i32 = WIN_SCORE + 1;
+
+/// The time is checked after this number of nodes only.
+const NODES_BETWEEN_TIME_CHECKS: u64 = 1024;
+
+/// The transposition table is cleared when it contains more entries.
+const MAX_TRANSPOSITIONS: usize = 1 << 20;
+
+/// Scores of the search are the values of the evaluator multiplied by this factor (and
+/// rounded), e.g. 100 per stone for `StoneDifference`.
+pub const SCORE_PER_UNIT: f32 = 100.0;
+
+/// Score of a finished game for the player to move in `state`, `ply` plies after the root.
+fn terminal_score(outcome: Outcome, state: &GameState, ply: u32) -> i32 {
+    match outcome.winner() {
+        Some(winner) if winner == state.curr_player => WIN_SCORE - ply as i32,
+        Some(_) => -(WIN_SCORE - ply as i32),
+        None => 0,
+    }
+}
+
+/// Which kind of score is stored in the transposition table.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bound {
+    Exact,
+    /// The score is at least the stored one (the search failed high).
+    Lower,
+    /// The score is at most the stored one (the search failed low).
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TranspositionEntry {
+    depth: u32,
+    /// Score relative to the position of the entry (wins count the plies from this position).
+    score: i32,
+    bound: Bound,
+    best_move: Option<usize>,
+}
+
+/// Negamax search with alpha-beta pruning, iterative deepening and a transposition table.
+///
+/// Moves are ordered by the best move of the transposition table first and then by the number
+/// of captured stones. Leaves are scored by an `Evaluator`. Unlike the playout based
+/// searches, the result only depends on the position and the depth, so the same position
+/// always gets the same move.
+pub struct AlphaBetaSearch {
+    root: GameState,
+    evaluator: Rc<dyn Evaluator>,
+    transpositions: HashMap<u64, TranspositionEntry>,
+    /// Exact scores of the moves from the root of the last completed iteration.
+    root_scores: Vec<Option<i32>>,
+    /// Number of searched nodes after every move from the root.
+    root_nodes: Vec<u32>,
+    best_move: Option<usize>,
+    completed_depth: u32,
+    nodes: u64,
+    starttime: Instant,
+    /// Time limit of the running iteration, the first iteration is never aborted.
+    max_time_in_millis: Option<u128>,
+    aborted: bool,
+}
+
+impl AlphaBetaSearch {
+    /// Search which scores the leaves by the difference of stones.
+    pub fn new(state: GameState) -> Self {
+        AlphaBetaSearch::with_evaluator(state, Rc::new(StoneDifference))
+    }
+
+    pub fn with_evaluator(state: GameState, evaluator: Rc<dyn Evaluator>) -> Self {
+        let number_of_cells = state.rules.number_of_cells();
+        AlphaBetaSearch {
+            root: state,
+            evaluator,
+            transpositions: HashMap::new(),
+            root_scores: vec![None; number_of_cells],
+            root_nodes: vec![0; number_of_cells],
+            best_move: None,
+            completed_depth: 0,
+            nodes: 0,
+            starttime: Instant::now(),
+            max_time_in_millis: None,
+            aborted: false,
+        }
+    }
+
+    /// Search with increasing depth until `max_depth` is reached or the time of the budget is
+    /// over (the limit of playouts is not used). An iteration which is aborted is discarded,
+    /// but the first one is always completed. Returns the depth of the last completed
+    /// iteration.
+    pub fn run(&mut self, max_depth: u32, budget: &SearchBudget) -> u32 {
+        self.starttime = Instant::now();
+        for depth in (self.completed_depth + 1)..=max_depth.max(1) {
+            self.max_time_in_millis = if depth == 1 {
+                None
+            } else {
+                budget.max_time_in_millis()
+            };
+            self.aborted = false;
+            if !self.search_root(depth) {
+                break;
+            }
+            self.completed_depth = depth;
+        }
+        self.completed_depth
+    }
+
+    /// Continue with the position after the move from `position`. The search starts again at
+    /// depth 1, but the transposition table is kept. Fails if the move is illegal.
+    pub fn advance(&mut self, position: usize) -> Result<(), GameError> {
+        self.root = self.root.make_move(position)?;
+        let number_of_cells = self.root.rules.number_of_cells();
+        self.root_scores = vec![None; number_of_cells];
+        self.root_nodes = vec![0; number_of_cells];
+        self.best_move = None;
+        self.completed_depth = 0;
+        Ok(())
+    }
+
+    /// Searches all moves from the root with the given depth. Returns false if the time was
+    /// over before the iteration was completed.
+    ///
+    /// Every move is searched with the full window, so the scores of all moves are exact and not
+    /// only upper bounds for the moves worse than the best one (`difficulty::move_values`
+    /// compares them).
+    fn search_root(&mut self, depth: u32) -> bool {
+        let mut scores = vec![None; self.root_scores.len()];
+        let mut best_move = None;
+        let mut alpha = -INFINITY;
+        let root = self.root.clone();
+        for (position, child) in ordered_moves(&root, self.best_move) {
+            let nodes_before = self.nodes;
+            let score = match self.negamax(&child, depth - 1, 1, -INFINITY, INFINITY) {
+                Some(score) => -score,
+                None => return false,
+            };
+            self.root_nodes[position] += (self.nodes - nodes_before) as u32;
+            scores[position] = Some(score);
+            if score > alpha {
+                alpha = score;
+                best_move = Some(position);
+            }
+        }
+        self.root_scores = scores;
+        self.best_move = best_move;
+        true
+    }
+
+    /// Score of `state` for the player to move or `None` if the search was aborted.
+    fn negamax(
+        &mut self,
+        state: &GameState,
+        depth: u32,
+        ply: u32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> Option<i32> {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(NODES_BETWEEN_TIME_CHECKS) && self.is_time_over() {
+            self.aborted = true;
+        }
+        if self.aborted {
+            return None;
+        }
+
+        if let Some(outcome) = state.outcome() {
+            return Some(terminal_score(outcome, state, ply));
+        }
+        if depth == 0 {
+            return Some(self.static_score(state));
+        }
+
+        let mut table_move = None;
+        let use_table_score = !reaches_capture_limit(state, depth);
+        if let Some(entry) = self.transpositions.get(&state.hash()) {
+            table_move = entry.best_move;
+            if use_table_score && entry.depth >= depth {
+                let score = score_from_table(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return Some(score),
+                    Bound::Lower if score >= beta => return Some(score),
+                    Bound::Upper if score <= alpha => return Some(score),
+                    _ => {}
+                }
+            }
+        }
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+        for (position, child) in ordered_moves(state, table_move) {
+            let score = -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha)?;
+            if score > best_score {
+                best_score = score;
+                best_move = Some(position);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        if best_move.is_none() {
+            // Every move would repeat the position, nothing to search
+            return Some(self.static_score(state));
+        }
+
+        if !use_table_score {
+            return Some(best_score);
+        }
+        let bound = if best_score <= original_alpha {
+            Bound::Upper
+        } else if best_score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+        if self.transpositions.len() >= MAX_TRANSPOSITIONS {
+            self.transpositions.clear();
+        }
+        self.transpositions.insert(
+            state.hash(),
+            TranspositionEntry {
+                depth,
+                score: score_to_table(best_score, ply),
+                bound,
+                best_move,
+            },
+        );
+        Some(best_score)
+    }
+
+    /// Value of the evaluator for the player to move, always less than a win.
+    fn static_score(&self, state: &GameState) -> i32 {
+        let value = self.evaluator.evaluate(state, state.curr_player) * SCORE_PER_UNIT;
+        (value.round() as i32).clamp(-MIN_WIN_SCORE, MIN_WIN_SCORE)
+    }
+
+    fn is_time_over(&self) -> bool {
+        match self.max_time_in_millis {
+            Some(max_time_in_millis) => self.starttime.elapsed().as_millis() >= max_time_in_millis,
+            None => false,
+        }
+    }
+
+    /// The best move of the last completed iteration or `None` if there is no possible move.
+    pub fn best_move(&self) -> Option<usize> {
+        self.best_move
+    }
+
+    /// Depth of the last completed iteration.
+    pub fn completed_depth(&self) -> u32 {
+        self.completed_depth
+    }
+
+    /// Number of searched nodes.
+    pub fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    /// Scores of the moves from the root for the player who is to move there (`None` for
+    /// illegal moves), see `root_scores`.
+    pub fn root_scores(&self) -> &[Option<i32>] {
+        &self.root_scores
+    }
+
+    /// Scores of the moves from the root, the best move is recommended.
+    pub fn evaluation(&self) -> Evaluation {
+        Evaluation::from_scores(
+            self.root.curr_player,
+            &self.root_scores,
+            &self.root_nodes,
+            self.best_move,
+        )
+    }
+}
+
+/// Legal moves of `state`, `first_move` first and then by the number of captured stones.
+fn ordered_moves(state: &GameState, first_move: Option<usize>) -> Vec<(usize, GameState)> {
+    let mut moves: Vec<(usize, GameState)> = state.legal_moves().collect();
+    moves.sort_by_key(|(position, child)| {
+        (
+            Some(*position) != first_move,
+            Reverse(child.move_statistic.stones_of_opponent_removed),
+        )
+    });
+    moves
+}
+
+/// True if the game may be drawn by the number of plies without capture within `depth` plies.
+/// The hash does not contain the number of plies without capture, so the scores of such
+/// searches are neither stored in nor taken from the transposition table.
+fn reaches_capture_limit(state: &GameState, depth: u32) -> bool {
+    let max_plies_without_capture = state.rules().max_plies_without_capture();
+    max_plies_without_capture > 0
+        && state.plies_without_capture() + depth >= max_plies_without_capture
+}
+
+/// Score of a win relative to the current node instead of the root.
+fn score_to_table(score: i32, ply: u32) -> i32 {
+    if score > MIN_WIN_SCORE {
+        score + ply as i32
+    } else if score < -MIN_WIN_SCORE {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+/// Inverse of `score_to_table`.
+fn score_from_table(score: i32, ply: u32) -> i32 {
+    if score > MIN_WIN_SCORE {
+        score - ply as i32
+    } else if score < -MIN_WIN_SCORE {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+/// Run an alpha-beta search from `state` and return the evaluation of the moves from `state`.
+pub fn alpha_beta_search(
+    state: GameState,
+    evaluator: Rc<dyn Evaluator>,
+    max_depth: u32,
+    budget: &SearchBudget,
+) -> Result<Evaluation, GameError> {
+    if state.outcome().is_some() {
+        return Err(GameError::NoMoveFound);
+    }
+    let mut search = AlphaBetaSearch::with_evaluator(state, evaluator);
+    search.run(max_depth, budget);
+    Ok(search.evaluation())
+}
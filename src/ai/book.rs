@@ -0,0 +1,314 @@
+//! Opening book: good moves of the first positions of a game, so the AI does not have to search
+//! them every game and varies its openings.
+//!
+//! A book is generated offline (see `OpeningBook::generate`): the positions from the initial
+//! position on are searched by an AI, the moves which are almost as good as the best one are
+//! stored with a weight and their positions are searched in turn, up to a number of plies. The
+//! AI chooses one of the moves of a position at random with the probabilities given by the
+//! weights (see `OpeningBook::choose`).
+//!
+//! The moves are stored by the hash of the position (see `GameState::hash`), so a position which
+//! is reached by different move orders is only stored once. A book is stored as a compact file
+//! (see `to_bytes`): a small header and 11 bytes per move.
+
+use crate::ai::budget::SearchBudget;
+use crate::ai::difficulty::move_values;
+use crate::ai::rng::Rng;
+use crate::ai::AI;
+use crate::{GameError, GameState, RuleSet};
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use wasm_bindgen::prelude::*;
+
+/// First bytes of an opening book file.
+pub const BOOK_MAGIC: &[u8; 4] = b"BOAB";
+
+/// Version of the file format written by `to_bytes`.
+const FORMAT_VERSION: u8 = 1;
+
+/// Length of the header: magic, version and length of the field.
+const HEADER_LENGTH: usize = BOOK_MAGIC.len() + 2;
+
+/// Length of a move in the file: hash, position and weight.
+const MOVE_LENGTH: usize = 11;
+
+/// Weight of the best move of a position, worse moves have smaller weights.
+pub const MAX_WEIGHT: u16 = 1000;
+
+/// A move of the book.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BookMove {
+    /// Hash of the position before the move.
+    pub hash: u64,
+    pub position: u8,
+    /// The move is chosen with a probability proportional to the weight.
+    pub weight: u16,
+}
+
+/// Errors while reading an opening book.
+#[derive(Debug, PartialEq, Clone)]
+pub enum BookError {
+    /// The data does not start with `BOOK_MAGIC`.
+    NotABook,
+    UnsupportedVersion(u8),
+    InvalidLengthOfField(usize),
+    /// The moves do not fill the data completely.
+    WrongSize(usize),
+    /// The moves are not ordered by hash and position, the index of the first wrong move.
+    UnorderedMoves(usize),
+}
+
+impl Display for BookError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            BookError::NotABook => write!(f, "the data is not an opening book"),
+            BookError::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {} of the opening book", version)
+            }
+            BookError::InvalidLengthOfField(length) => {
+                write!(f, "invalid length of the field {}", length)
+            }
+            BookError::WrongSize(size) => write!(f, "wrong size of {} bytes", size),
+            BookError::UnorderedMoves(index) => write!(f, "move {} is out of order", index),
+        }
+    }
+}
+
+/// Settings of `OpeningBook::generate`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct BookSettings {
+    /// Number of plies from the initial position which are searched.
+    pub max_plies: u32,
+    /// Maximal number of moves per position.
+    pub max_moves: usize,
+    /// Moves whose value (between -1 and 1, see `difficulty::move_values`) is at most this much
+    /// worse than the one of the best move are stored.
+    pub margin: f64,
+    /// Budget of the search of every position.
+    pub budget: SearchBudget,
+}
+
+impl Default for BookSettings {
+    fn default() -> Self {
+        BookSettings {
+            max_plies: 6,
+            max_moves: 3,
+            margin: 0.1,
+            budget: SearchBudget::playouts(1000),
+        }
+    }
+}
+
+/// Moves of the opening, see the module documentation.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct OpeningBook {
+    length_of_field: usize,
+    /// Ordered by hash and position.
+    moves: Vec<BookMove>,
+}
+
+#[wasm_bindgen]
+impl OpeningBook {
+    /// Reads an opening book written by `to_bytes`, e.g. fetched from the server.
+    pub fn from_bytes_wasm(bytes: &[u8]) -> Result<OpeningBook, JsValue> {
+        OpeningBook::from_bytes(bytes)
+            .map_err(|e| JsValue::from(format!("Invalid opening book: {}", e).as_str()))
+    }
+
+    /// The book as file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + MOVE_LENGTH * self.moves.len());
+        bytes.extend_from_slice(BOOK_MAGIC);
+        bytes.push(FORMAT_VERSION);
+        bytes.push(self.length_of_field as u8);
+        for book_move in self.moves.iter() {
+            bytes.extend_from_slice(&book_move.hash.to_le_bytes());
+            bytes.push(book_move.position);
+            bytes.extend_from_slice(&book_move.weight.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length_of_field(&self) -> usize {
+        self.length_of_field
+    }
+
+    /// Number of moves in the book.
+    pub fn size(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Number of positions with moves in the book.
+    pub fn number_of_positions(&self) -> usize {
+        self.moves
+            .windows(2)
+            .filter(|pair| pair[0].hash != pair[1].hash)
+            .count()
+            + usize::from(!self.moves.is_empty())
+    }
+
+    /// True if the book contains a legal move in `state`.
+    pub fn contains(&self, state: &GameState) -> bool {
+        !self.moves_of(state).is_empty()
+    }
+}
+
+impl OpeningBook {
+    /// Empty book for a board with `length_of_field` cells per row.
+    pub fn new(length_of_field: usize) -> Result<OpeningBook, GameError> {
+        RuleSet::new(length_of_field)?;
+        Ok(OpeningBook {
+            length_of_field,
+            moves: vec![],
+        })
+    }
+
+    /// Searches the positions from `initial_state` on with `ai` as described in the module
+    /// documentation. Takes the time of a search per stored position, every position gets a new
+    /// search whether `ai` reuses its searches or not. The moves are compared by their values, so
+    /// the search has to value all moves and not only the best one (the alpha-beta search gives
+    /// exact scores for all moves from the root).
+    pub fn generate(
+        ai: &AI,
+        initial_state: &GameState,
+        settings: &BookSettings,
+    ) -> Result<OpeningBook, GameError> {
+        let mut book = OpeningBook::new(initial_state.rules().length_of_field())?;
+        let mut queue = VecDeque::from(vec![(initial_state.clone(), 0)]);
+        while let Some((state, ply)) = queue.pop_front() {
+            if ply >= settings.max_plies || state.outcome().is_some() || book.contains(&state) {
+                continue;
+            }
+            // A fresh search for every position: a search kept by `ai` would make the values
+            // depend on the order in which the positions are searched
+            let mut search = ai.search(&state)?;
+            search.run(&settings.budget);
+            let mut values = move_values(&search.evaluation());
+            values.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let best_value = match values.first() {
+                Some((_, value)) => *value,
+                None => continue,
+            };
+            for (position, value) in values.into_iter().take(settings.max_moves) {
+                let shortfall = best_value - value;
+                if shortfall > settings.margin {
+                    break;
+                }
+                let share = if settings.margin > 0.0 {
+                    1.0 - shortfall / settings.margin
+                } else {
+                    1.0
+                };
+                let weight = 1 + ((MAX_WEIGHT - 1) as f64 * share).round() as u16;
+                book.add(&state, position, weight)?;
+                queue.push_back((state.make_move(position)?, ply + 1));
+            }
+        }
+        Ok(book)
+    }
+
+    /// Adds a move or changes its weight. Fails if the move is illegal.
+    pub fn add(
+        &mut self,
+        state: &GameState,
+        position: usize,
+        weight: u16,
+    ) -> Result<(), GameError> {
+        if state.rules().length_of_field() != self.length_of_field {
+            return Err(GameError::InvalidRuleSet);
+        }
+        state.make_move(position)?;
+        let book_move = BookMove {
+            hash: state.hash(),
+            position: position as u8,
+            weight,
+        };
+        let key = (book_move.hash, book_move.position);
+        match self
+            .moves
+            .binary_search_by_key(&key, |book_move| (book_move.hash, book_move.position))
+        {
+            Ok(index) => self.moves[index].weight = weight,
+            Err(index) => self.moves.insert(index, book_move),
+        }
+        Ok(())
+    }
+
+    /// Reads an opening book written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<OpeningBook, BookError> {
+        if bytes.len() < HEADER_LENGTH || !bytes.starts_with(BOOK_MAGIC) {
+            return Err(BookError::NotABook);
+        }
+        let header = &bytes[BOOK_MAGIC.len()..HEADER_LENGTH];
+        if header[0] != FORMAT_VERSION {
+            return Err(BookError::UnsupportedVersion(header[0]));
+        }
+        let length_of_field = header[1] as usize;
+        let mut book = OpeningBook::new(length_of_field)
+            .map_err(|_| BookError::InvalidLengthOfField(length_of_field))?;
+
+        let data = &bytes[HEADER_LENGTH..];
+        if !data.len().is_multiple_of(MOVE_LENGTH) {
+            return Err(BookError::WrongSize(bytes.len()));
+        }
+        for (index, data) in data.chunks_exact(MOVE_LENGTH).enumerate() {
+            let mut hash = [0; 8];
+            hash.copy_from_slice(&data[..8]);
+            let book_move = BookMove {
+                hash: u64::from_le_bytes(hash),
+                position: data[8],
+                weight: u16::from_le_bytes([data[9], data[10]]),
+            };
+            if let Some(last) = book.moves.last() {
+                if (last.hash, last.position) >= (book_move.hash, book_move.position) {
+                    return Err(BookError::UnorderedMoves(index));
+                }
+            }
+            book.moves.push(book_move);
+        }
+        Ok(book)
+    }
+
+    /// All moves of the book.
+    pub fn moves(&self) -> &[BookMove] {
+        &self.moves
+    }
+
+    /// The legal moves of the book in `state`.
+    pub fn moves_of(&self, state: &GameState) -> Vec<BookMove> {
+        if state.rules().length_of_field() != self.length_of_field {
+            return vec![];
+        }
+        let hash = state.hash();
+        let start = self
+            .moves
+            .partition_point(|book_move| book_move.hash < hash);
+        self.moves[start..]
+            .iter()
+            .take_while(|book_move| book_move.hash == hash)
+            // Different positions may have the same hash
+            .filter(|book_move| state.is_legal_move(book_move.position as usize))
+            .copied()
+            .collect()
+    }
+
+    /// One of the moves of the book in `state` chosen at random with the probabilities given by
+    /// the weights, `None` if the book contains no move in `state`.
+    pub fn choose(&self, state: &GameState, rng: &mut Rng) -> Option<usize> {
+        let moves = self.moves_of(state);
+        let total: u32 = moves.iter().map(|book_move| book_move.weight as u32).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut remaining = rng.gen_range(0, total as usize) as u32;
+        for book_move in moves {
+            if remaining < book_move.weight as u32 {
+                return Some(book_move.position as usize);
+            }
+            remaining -= book_move.weight as u32;
+        }
+        None
+    }
+}
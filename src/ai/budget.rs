@@ -0,0 +1,97 @@
+use instant::Instant;
+use wasm_bindgen::prelude::*;
+
+/// How long the AI may think: at most a number of playouts, at most some time or both (then
+/// the search stops at whatever limit is reached first).
+///
+/// Uses `instant::Instant` which works in the browser as well.
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SearchBudget {
+    max_playouts: Option<u32>,
+    max_time_in_millis: Option<u128>,
+}
+
+impl Default for SearchBudget {
+    fn default() -> Self {
+        SearchBudget::playouts(100)
+    }
+}
+
+impl SearchBudget {
+    /// Create a budget from optional limits. Without any limit, the default budget is used.
+    pub fn new(max_playouts: Option<u32>, max_time_in_millis: Option<u128>) -> Self {
+        if max_playouts.is_none() && max_time_in_millis.is_none() {
+            return SearchBudget::default();
+        }
+        SearchBudget {
+            max_playouts,
+            max_time_in_millis,
+        }
+    }
+
+    pub fn max_time_in_millis(&self) -> Option<u128> {
+        self.max_time_in_millis
+    }
+
+    /// Same budget, but the playout limit multiplied by `factor` (e.g. the number of possible
+    /// moves if the limit is given per move). Saturates at `u32::MAX`.
+    pub fn scale_playouts(&self, factor: u32) -> Self {
+        SearchBudget {
+            max_playouts: self
+                .max_playouts
+                .map(|max_playouts| max_playouts.saturating_mul(factor)),
+            max_time_in_millis: self.max_time_in_millis,
+        }
+    }
+
+    /// True if `playouts_done` playouts reached the limit or the time since `starttime` is over.
+    pub fn is_exhausted(&self, playouts_done: u32, starttime: &Instant) -> bool {
+        if let Some(max_playouts) = self.max_playouts {
+            if playouts_done >= max_playouts {
+                return true;
+            }
+        }
+
+        if let Some(max_time_in_millis) = self.max_time_in_millis {
+            if starttime.elapsed().as_millis() >= max_time_in_millis {
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+#[wasm_bindgen]
+impl SearchBudget {
+    /// Limit of the playouts or `undefined` if there is none.
+    pub fn max_playouts(&self) -> Option<u32> {
+        self.max_playouts
+    }
+
+    /// Stop after `max_playouts` playouts.
+    pub fn playouts(max_playouts: u32) -> SearchBudget {
+        SearchBudget {
+            max_playouts: Some(max_playouts),
+            max_time_in_millis: None,
+        }
+    }
+
+    /// Stop after `max_time_in_millis` milliseconds.
+    pub fn time(max_time_in_millis: u32) -> SearchBudget {
+        SearchBudget {
+            max_playouts: None,
+            max_time_in_millis: Some(max_time_in_millis as u128),
+        }
+    }
+
+    /// Stop after `max_playouts` playouts or `max_time_in_millis` milliseconds, whatever comes
+    /// first.
+    pub fn playouts_or_time(max_playouts: u32, max_time_in_millis: u32) -> SearchBudget {
+        SearchBudget {
+            max_playouts: Some(max_playouts),
+            max_time_in_millis: Some(max_time_in_millis as u128),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::ai::alphabeta::SCORE_PER_UNIT;
+use crate::ai::budget::SearchBudget;
+use crate::ai::evaluation::Evaluation;
+use crate::ai::playout::PlayoutPolicyKind;
+use crate::ai::rng::Rng;
+use crate::ai::Algorithm;
+use wasm_bindgen::prelude::*;
+
+/// Named strength of the AI, from a beginner who makes many mistakes to the strongest setting.
+///
+/// A level combines the search (see `DifficultySettings`) with deliberate noise in the choice
+/// of the move (see `MoveChoice`), so weaker levels still play sensible moves most of the time.
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Difficulty {
+    Beginner,
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+}
+
+/// Everything a difficulty level changes in the AI.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DifficultySettings {
+    pub algorithm: Algorithm,
+    pub playout_policy: PlayoutPolicyKind,
+    /// Budget of a single move, the playouts are counted per possible move.
+    pub budget: SearchBudget,
+    pub move_choice: MoveChoice,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 5] = [
+        Difficulty::Beginner,
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Expert,
+    ];
+
+    pub fn settings(self) -> DifficultySettings {
+        let (algorithm, playout_policy, playouts, temperature, blunder_probability) = match self {
+            Difficulty::Beginner => (
+                Algorithm::FlatMonteCarlo,
+                PlayoutPolicyKind::UniformRandom,
+                100,
+                0.5,
+                0.35,
+            ),
+            Difficulty::Easy => (
+                Algorithm::FlatMonteCarlo,
+                PlayoutPolicyKind::UniformRandom,
+                200,
+                0.25,
+                0.2,
+            ),
+            Difficulty::Medium => (
+                Algorithm::MonteCarloTreeSearch,
+                PlayoutPolicyKind::UniformRandom,
+                500,
+                0.1,
+                0.05,
+            ),
+            Difficulty::Hard => (
+                Algorithm::MonteCarloTreeSearch,
+                PlayoutPolicyKind::GreedyCapture,
+                2000,
+                0.0,
+                0.0,
+            ),
+            Difficulty::Expert => (
+                Algorithm::MonteCarloTreeSearch,
+                PlayoutPolicyKind::GreedyCapture,
+                5000,
+                0.0,
+                0.0,
+            ),
+        };
+        DifficultySettings {
+            algorithm,
+            playout_policy,
+            budget: SearchBudget::playouts(playouts),
+            move_choice: MoveChoice {
+                temperature,
+                blunder_probability,
+            },
+        }
+    }
+}
+
+/// Score of the alpha-beta search (in stones) which is worth a value of about 0.76 in
+/// `move_values`, like a position with 88% won playouts.
+const STONES_PER_VALUE: f64 = 4.0;
+
+/// How the move is chosen from the evaluation of a search.
+///
+/// Without noise (the default), the recommended move is played. With a positive
+/// `temperature`, a move is chosen with a probability proportional to
+/// `exp(value / temperature)`, where the values are given by `move_values`. Additionally, with
+/// probability `blunder_probability` a random other move than the recommended one is played.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct MoveChoice {
+    pub temperature: f64,
+    pub blunder_probability: f64,
+}
+
+impl MoveChoice {
+    /// Chosen move or `None` if the evaluation contains no possible move.
+    pub fn choose(&self, evaluation: &Evaluation, rng: &mut Rng) -> Option<usize> {
+        let recommended_move = evaluation.recommended_move();
+        let values = move_values(evaluation);
+        if values.is_empty() {
+            return recommended_move;
+        }
+
+        if self.blunder_probability > 0.0 && rng.gen_fraction() < self.blunder_probability {
+            let other_moves: Vec<usize> = values
+                .iter()
+                .map(|(position, _)| *position)
+                .filter(|position| Some(*position) != recommended_move)
+                .collect();
+            if !other_moves.is_empty() {
+                return Some(other_moves[rng.gen_range(0, other_moves.len())]);
+            }
+        }
+
+        if self.temperature <= 0.0 {
+            return recommended_move.or(Some(values[0].0));
+        }
+        let max_value = values
+            .iter()
+            .map(|(_, value)| *value)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = values
+            .iter()
+            .map(|(_, value)| ((value - max_value) / self.temperature).exp())
+            .collect();
+        let mut remaining = rng.gen_fraction() * weights.iter().sum::<f64>();
+        for ((position, _), weight) in values.iter().zip(weights.iter()) {
+            remaining -= weight;
+            if remaining < 0.0 {
+                return Some(*position);
+            }
+        }
+        values.last().map(|(position, _)| *position)
+    }
+}
+
+/// Values in [-1, 1] of the searched moves of an evaluation for the player to move, higher is
+/// better. Moves with playouts are valued by the mean result of their playouts (win 1, draw 0,
+/// loss -1), moves of the alpha-beta search by their exact score (see `STONES_PER_VALUE`).
+pub fn move_values(evaluation: &Evaluation) -> Vec<(usize, f64)> {
+    let wins = evaluation.wins();
+    let losses = evaluation.losses();
+    // Evaluations from older versions may have no draws and scores
+    let draws = evaluation.draws();
+    let scores = evaluation.scores();
+    evaluation
+        .visits()
+        .iter()
+        .enumerate()
+        .filter(|(_, visits)| **visits > 0)
+        .map(|(position, _)| {
+            let draws = draws.get(position).copied().unwrap_or(0);
+            let playouts = wins[position] + losses[position] + draws;
+            let value = if playouts > 0 {
+                (wins[position] as f64 - losses[position] as f64) / playouts as f64
+            } else {
+                let score = scores.get(position).copied().unwrap_or(0);
+                let stones = score as f64 / SCORE_PER_UNIT as f64;
+                (stones / STONES_PER_VALUE).tanh()
+            };
+            (position, value)
+        })
+        .collect()
+}
@@ -0,0 +1,246 @@
+use crate::Outcome;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use wasm_bindgen::prelude::*;
+
+/// Results of the playouts after one move, counted per player.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PlayoutCounts {
+    /// Number of playouts won by player 0 and player 1.
+    pub wins: [u32; 2],
+    pub draws: u32,
+}
+
+impl PlayoutCounts {
+    pub fn record(&mut self, outcome: Outcome) {
+        match outcome.winner() {
+            Some(winner) => self.wins[winner as usize] += 1,
+            None => self.draws += 1,
+        }
+    }
+
+    /// Number of all counted playouts.
+    pub fn total(&self) -> u32 {
+        self.wins[0] + self.wins[1] + self.draws
+    }
+}
+
+/// Result of an AI evaluation of a game state. All statistics are given per position on the
+/// field of the player the evaluation was computed for and from his/her point of view.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedEvaluation")]
+pub struct Evaluation {
+    /// Player (0 or 1) who is to move in the evaluated state.
+    player: u8,
+    /// Number of playouts won by `player` after moving from a position.
+    wins: Vec<u32>,
+    /// Number of playouts lost by `player` after moving from a position.
+    losses: Vec<u32>,
+    /// Number of drawn playouts after moving from a position.
+    #[serde(default)]
+    draws: Vec<u32>,
+    /// Number of playouts done (or nodes searched) after moving from a position.
+    visits: Vec<u32>,
+    /// Value of moving from a position for `player`, higher is better: the difference of wins
+    /// and losses for the playout based searches, the score for the alpha-beta search.
+    #[serde(default)]
+    scores: Vec<i32>,
+    recommended_move: Option<usize>,
+}
+
+/// Deserialized evaluation which still has to be checked, see `Evaluation::try_from`.
+#[derive(Deserialize)]
+struct UncheckedEvaluation {
+    player: u8,
+    wins: Vec<u32>,
+    losses: Vec<u32>,
+    #[serde(default)]
+    draws: Vec<u32>,
+    visits: Vec<u32>,
+    #[serde(default)]
+    scores: Vec<i32>,
+    recommended_move: Option<usize>,
+}
+
+impl TryFrom<UncheckedEvaluation> for Evaluation {
+    type Error = String;
+
+    /// Accepts only evaluations with statistics for every cell: the wins, losses and visits
+    /// have the same length (the number of cells), and so do the draws and scores unless they
+    /// are missing (evaluations from older versions).
+    fn try_from(unchecked: UncheckedEvaluation) -> Result<Self, Self::Error> {
+        let number_of_cells = unchecked.visits.len();
+        if unchecked.player > 1 {
+            return Err(format!("Invalid player {}", unchecked.player));
+        }
+        let lengths = [
+            ("wins", unchecked.wins.len(), false),
+            ("losses", unchecked.losses.len(), false),
+            ("draws", unchecked.draws.len(), true),
+            ("scores", unchecked.scores.len(), true),
+        ];
+        for (name, length, optional) in lengths.iter() {
+            if *length != number_of_cells && !(*optional && *length == 0) {
+                return Err(format!(
+                    "{} has {} values, but there are {} cells",
+                    name, length, number_of_cells
+                ));
+            }
+        }
+        if let Some(position) = unchecked.recommended_move {
+            if position >= number_of_cells {
+                return Err(format!("Recommended move {} outside of field", position));
+            }
+        }
+
+        Ok(Evaluation {
+            player: unchecked.player,
+            wins: unchecked.wins,
+            losses: unchecked.losses,
+            draws: unchecked.draws,
+            visits: unchecked.visits,
+            scores: unchecked.scores,
+            recommended_move: unchecked.recommended_move,
+        })
+    }
+}
+
+impl Evaluation {
+    /// Create an evaluation from the playout results for every position (the format returned by
+    /// `game_playout`). If `recommended_move` is `None`, the visited position with the highest
+    /// difference of wins and losses is recommended.
+    pub fn from_playout_counts(
+        player: u8,
+        counts: &[PlayoutCounts],
+        recommended_move: Option<usize>,
+    ) -> Self {
+        let opponent = 1 - player as usize;
+        let mut evaluation = Evaluation {
+            player,
+            wins: counts.iter().map(|c| c.wins[player as usize]).collect(),
+            losses: counts.iter().map(|c| c.wins[opponent]).collect(),
+            draws: counts.iter().map(|c| c.draws).collect(),
+            visits: counts.iter().map(PlayoutCounts::total).collect(),
+            scores: vec![],
+            recommended_move,
+        };
+        evaluation.scores = evaluation.win_differences();
+        if evaluation.recommended_move.is_none() {
+            evaluation.recommended_move = evaluation.move_with_highest_win_difference();
+        }
+        evaluation
+    }
+
+    /// Create an evaluation from the number of wins of player 0 and player 1 for every position
+    /// (no drawn playouts).
+    pub fn from_wins_losses(
+        player: u8,
+        wins_losses: &[(u32, u32)],
+        recommended_move: Option<usize>,
+    ) -> Self {
+        let counts: Vec<PlayoutCounts> = wins_losses
+            .iter()
+            .map(|v| PlayoutCounts {
+                wins: [v.0, v.1],
+                draws: 0,
+            })
+            .collect();
+        Evaluation::from_playout_counts(player, &counts, recommended_move)
+    }
+
+    /// Create an evaluation from the scores of a search (`None` for impossible moves) and the
+    /// number of nodes searched after every move. There are no wins and losses.
+    pub fn from_scores(
+        player: u8,
+        scores: &[Option<i32>],
+        nodes: &[u32],
+        recommended_move: Option<usize>,
+    ) -> Self {
+        Evaluation {
+            player,
+            wins: vec![0; scores.len()],
+            losses: vec![0; scores.len()],
+            draws: vec![0; scores.len()],
+            visits: nodes.to_vec(),
+            scores: scores.iter().map(|score| score.unwrap_or(0)).collect(),
+            recommended_move,
+        }
+    }
+
+    fn move_with_highest_win_difference(&self) -> Option<usize> {
+        self.win_differences()
+            .iter()
+            .enumerate()
+            .filter(|(position, _)| self.visits[*position] > 0)
+            .max_by_key(|(_, difference)| **difference)
+            .map(|(position, _)| position)
+    }
+}
+
+#[wasm_bindgen]
+impl Evaluation {
+    #[wasm_bindgen(getter)]
+    pub fn player(&self) -> u8 {
+        self.player
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn wins(&self) -> Vec<i32> {
+        counts_to_i32(&self.wins)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn losses(&self) -> Vec<i32> {
+        counts_to_i32(&self.losses)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn draws(&self) -> Vec<i32> {
+        counts_to_i32(&self.draws)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn visits(&self) -> Vec<i32> {
+        counts_to_i32(&self.visits)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn scores(&self) -> Vec<i32> {
+        self.scores.clone()
+    }
+
+    /// Best move found by the AI or `undefined` if there is no possible move.
+    #[wasm_bindgen(getter)]
+    pub fn recommended_move(&self) -> Option<usize> {
+        self.recommended_move
+    }
+
+    /// Wins minus losses for every position. Positive values are good for `player`.
+    pub fn win_differences(&self) -> Vec<i32> {
+        self.wins
+            .iter()
+            .zip(self.losses.iter())
+            .map(|(w, l)| *w as i32 - *l as i32)
+            .collect()
+    }
+
+    /// The evaluation as plain JS object.
+    pub fn to_js(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(self).map_err(JsValue::from)
+    }
+
+    /// Evaluation from a plain JS object created by `to_js`. Fails if the statistics are not
+    /// given for every cell.
+    pub fn from_js(value: JsValue) -> Result<Evaluation, JsValue> {
+        serde_wasm_bindgen::from_value(value).map_err(JsValue::from)
+    }
+}
+
+/// Counts as `i32` (an `Int32Array` in JS like the scores), saturating at `i32::MAX`.
+fn counts_to_i32(counts: &[u32]) -> Vec<i32> {
+    counts
+        .iter()
+        .map(|count| i32::try_from(*count).unwrap_or(i32::MAX))
+        .collect()
+}
@@ -0,0 +1,167 @@
+use crate::zobrist;
+use crate::GameState;
+use wasm_bindgen::prelude::*;
+
+/// Static evaluation of a position, i.e., without searching any moves.
+///
+/// Values are given for `player`, higher values are better for the player and the value for
+/// the opponent is usually the negation. Finished games are left to the search which uses the
+/// evaluator, so evaluators do not need to detect them.
+pub trait Evaluator {
+    fn evaluate(&self, state: &GameState, player: u8) -> f32;
+}
+
+/// Own stones minus the stones of the opponent.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StoneDifference;
+
+impl Evaluator for StoneDifference {
+    fn evaluate(&self, state: &GameState, player: u8) -> f32 {
+        let player = player as usize;
+        state.stones_of_player(player) as f32 - state.stones_of_player(1 - player) as f32
+    }
+}
+
+/// Like `StoneDifference`, but stones in the front row and in the back row are weighted
+/// differently. Stones in the back row cannot be captured, stones in the front row can capture
+/// stones of the opponent.
+#[derive(Debug, Clone, Copy)]
+pub struct RowWeighted {
+    pub front_row_weight: f32,
+    pub back_row_weight: f32,
+}
+
+impl Default for RowWeighted {
+    fn default() -> Self {
+        RowWeighted {
+            front_row_weight: 1.0,
+            back_row_weight: 1.25,
+        }
+    }
+}
+
+impl RowWeighted {
+    fn weighted_stones(&self, state: &GameState, player: usize) -> f32 {
+        let length_of_field = state.rules.length_of_field;
+        let cells = &state.fields_of_players[player].cells;
+        let front_row: u32 = cells[..length_of_field].iter().map(|s| *s as u32).sum();
+        let back_row: u32 = cells[length_of_field..].iter().map(|s| *s as u32).sum();
+        self.front_row_weight * front_row as f32 + self.back_row_weight * back_row as f32
+    }
+}
+
+impl Evaluator for RowWeighted {
+    fn evaluate(&self, state: &GameState, player: u8) -> f32 {
+        let player = player as usize;
+        self.weighted_stones(state, player) - self.weighted_stones(state, 1 - player)
+    }
+}
+
+/// Number of legal moves of the player minus the number of legal moves of the opponent (as if
+/// the opponent was to move).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mobility;
+
+impl Evaluator for Mobility {
+    fn evaluate(&self, state: &GameState, player: u8) -> f32 {
+        let moves = |player: u8| with_player_to_move(state, player).legal_moves().count() as f32;
+        moves(player) - moves(1 - player)
+    }
+}
+
+/// Minus the number of stones of the player which the opponent can capture with a single move
+/// (as if the opponent was to move).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Vulnerability;
+
+impl Evaluator for Vulnerability {
+    fn evaluate(&self, state: &GameState, player: u8) -> f32 {
+        let max_capture = with_player_to_move(state, 1 - player)
+            .legal_moves()
+            .map(|(_, new_state)| new_state.move_statistic.stones_of_opponent_removed)
+            .max()
+            .unwrap_or(0);
+        -(max_capture as f32)
+    }
+}
+
+/// Weighted sum of other evaluators, e.g. to combine the material with the mobility.
+#[derive(Default)]
+pub struct WeightedSum {
+    evaluators: Vec<(f32, Box<dyn Evaluator>)>,
+}
+
+impl WeightedSum {
+    pub fn new() -> Self {
+        WeightedSum::default()
+    }
+
+    /// Adds `evaluator` with the factor `weight`.
+    pub fn with(mut self, weight: f32, evaluator: impl Evaluator + 'static) -> Self {
+        self.evaluators.push((weight, Box::new(evaluator)));
+        self
+    }
+}
+
+impl Evaluator for WeightedSum {
+    fn evaluate(&self, state: &GameState, player: u8) -> f32 {
+        self.evaluators
+            .iter()
+            .map(|(weight, evaluator)| weight * evaluator.evaluate(state, player))
+            .sum()
+    }
+}
+
+/// The built-in evaluators, e.g. to choose one in the UI.
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EvaluatorKind {
+    StoneDifference,
+    RowWeighted,
+    Mobility,
+    Vulnerability,
+    /// Stone difference with small corrections for the mobility and the vulnerability.
+    Balanced,
+}
+
+impl EvaluatorKind {
+    pub const ALL: [EvaluatorKind; 5] = [
+        EvaluatorKind::StoneDifference,
+        EvaluatorKind::RowWeighted,
+        EvaluatorKind::Mobility,
+        EvaluatorKind::Vulnerability,
+        EvaluatorKind::Balanced,
+    ];
+
+    pub fn evaluator(self) -> Box<dyn Evaluator> {
+        match self {
+            EvaluatorKind::StoneDifference => Box::new(StoneDifference),
+            EvaluatorKind::RowWeighted => Box::new(RowWeighted::default()),
+            EvaluatorKind::Mobility => Box::new(Mobility),
+            EvaluatorKind::Vulnerability => Box::new(Vulnerability),
+            EvaluatorKind::Balanced => Box::new(
+                WeightedSum::new()
+                    .with(1.0, StoneDifference)
+                    .with(0.1, Mobility)
+                    .with(0.5, Vulnerability),
+            ),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl GameState {
+    /// Static evaluation of the position for `player` (0 or 1) by a built-in evaluator, higher
+    /// values are better for the player.
+    pub fn evaluate(&self, kind: EvaluatorKind, player: u8) -> f32 {
+        kind.evaluator().evaluate(self, player)
+    }
+}
+
+/// Copy of `state` where `player` is to move (with the hash updated accordingly).
+pub(crate) fn with_player_to_move(state: &GameState, player: u8) -> GameState {
+    let mut state = state.clone();
+    state.hash ^= zobrist::side_to_move_key(state.curr_player) ^ zobrist::side_to_move_key(player);
+    state.curr_player = player;
+    state
+}
@@ -0,0 +1,303 @@
+use crate::ai::budget::SearchBudget;
+use crate::ai::evaluation::{Evaluation, PlayoutCounts};
+use crate::ai::playout::{PlayoutPolicy, UniformRandom};
+use crate::ai::playout_with_policy;
+use crate::ai::rng::Rng;
+use crate::{GameError, GameState, Outcome};
+use instant::Instant;
+use std::rc::Rc;
+
+/// Default exploration constant of the UCT formula (sqrt(2), the theoretical value for rewards
+/// in [0, 1]).
+pub const DEFAULT_EXPLORATION_CONSTANT: f64 = std::f64::consts::SQRT_2;
+
+/// A node of the search tree. Every node except the root was reached by a move of the player who
+/// is *not* to move in `state`.
+struct Node {
+    state: GameState,
+    /// Position on the board (of the player who moved) of the move leading to this node.
+    move_position: usize,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Legal moves which were not expanded yet (only the positions are kept to save memory).
+    untried_moves: Vec<usize>,
+    visits: u32,
+    /// Number of playouts won by the player who made the move leading to this node.
+    wins: u32,
+    draws: u32,
+}
+
+impl Node {
+    fn new(state: GameState, move_position: usize, parent: Option<usize>) -> Self {
+        let untried_moves = state.legal_moves().map(|(position, _)| position).collect();
+
+        Node {
+            state,
+            move_position,
+            parent,
+            children: vec![],
+            untried_moves,
+            visits: 0,
+            wins: 0,
+            draws: 0,
+        }
+    }
+
+    /// The player who made the move leading to this node.
+    fn moved_player(&self) -> u8 {
+        (self.state.curr_player + 1) % 2
+    }
+}
+
+/// Monte Carlo tree search using the UCT rule (upper confidence bounds applied to trees).
+///
+/// Every iteration consists of the four classical steps: selection of a promising leaf,
+/// expansion of one untried move, simulation of a game via a `PlayoutPolicy` and
+/// backpropagation of the result up to the root.
+pub struct MonteCarloTreeSearch {
+    /// All nodes of the tree, the root is at index 0.
+    nodes: Vec<Node>,
+    exploration_constant: f64,
+    playout_policy: Rc<dyn PlayoutPolicy>,
+    rng: Rng,
+}
+
+impl MonteCarloTreeSearch {
+    /// Search with uniformly random playouts and random numbers which are not reproducible.
+    pub fn new(state: GameState, exploration_constant: f64) -> Self {
+        MonteCarloTreeSearch::with_policy(
+            state,
+            exploration_constant,
+            Rc::new(UniformRandom),
+            Rng::from_entropy(),
+        )
+    }
+
+    pub fn with_policy(
+        state: GameState,
+        exploration_constant: f64,
+        playout_policy: Rc<dyn PlayoutPolicy>,
+        rng: Rng,
+    ) -> Self {
+        MonteCarloTreeSearch {
+            nodes: vec![Node::new(state, 0, None)],
+            exploration_constant,
+            playout_policy,
+            rng,
+        }
+    }
+
+    /// Run iterations (one playout each) until the budget is exhausted. Returns the number of
+    /// done iterations.
+    pub fn run(&mut self, budget: &SearchBudget) -> u32 {
+        let starttime = Instant::now();
+        let mut iterations_counter = 0;
+
+        while !budget.is_exhausted(iterations_counter, &starttime) {
+            self.iterate();
+            iterations_counter += 1;
+        }
+
+        iterations_counter
+    }
+
+    /// Do a single selection - expansion - simulation - backpropagation step.
+    pub fn iterate(&mut self) {
+        let leaf = self.select();
+        let node = self.expand(leaf);
+
+        let outcome = match self.nodes[node].state.outcome() {
+            Some(outcome) => Ok(outcome),
+            None => playout_with_policy(
+                self.nodes[node].state.clone(),
+                self.playout_policy.as_ref(),
+                &mut self.rng,
+            ),
+        };
+
+        // Playouts which could not be completed are not counted (same as in `game_playout`).
+        if let Ok(outcome) = outcome {
+            self.backpropagate(node, outcome);
+        }
+    }
+
+    /// Descend from the root along the children with the highest UCT value until a node with
+    /// untried moves (or a terminal node) is reached.
+    fn select(&self) -> usize {
+        let mut node = 0;
+        while self.nodes[node].untried_moves.is_empty() && !self.nodes[node].children.is_empty() {
+            let log_visits = (self.nodes[node].visits.max(1) as f64).ln();
+            node = *self.nodes[node]
+                .children
+                .iter()
+                .max_by(|a, b| {
+                    self.uct_value(**a, log_visits)
+                        .partial_cmp(&self.uct_value(**b, log_visits))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .unwrap();
+        }
+        node
+    }
+
+    fn uct_value(&self, node: usize, log_parent_visits: f64) -> f64 {
+        let node = &self.nodes[node];
+        if node.visits == 0 {
+            return f64::INFINITY;
+        }
+        let visits = node.visits as f64;
+        // A draw is worth half a win
+        let reward = node.wins as f64 + 0.5 * node.draws as f64;
+        reward / visits + self.exploration_constant * (log_parent_visits / visits).sqrt()
+    }
+
+    /// Add a child for one (randomly chosen) untried move of `node`. Returns the new child or
+    /// `node` itself if no untried move is left.
+    fn expand(&mut self, node: usize) -> usize {
+        let untried_moves = &mut self.nodes[node].untried_moves;
+        if untried_moves.is_empty() {
+            return node;
+        }
+
+        let position = untried_moves.swap_remove(self.rng.gen_range(0, untried_moves.len()));
+        let newstate = self.nodes[node]
+            .state
+            .make_move(position)
+            .expect("Untried moves are legal moves.");
+        let child = self.nodes.len();
+        self.nodes.push(Node::new(newstate, position, Some(node)));
+        self.nodes[node].children.push(child);
+        child
+    }
+
+    fn backpropagate(&mut self, node: usize, outcome: Outcome) {
+        let mut curr_node = Some(node);
+        while let Some(node) = curr_node {
+            let node = &mut self.nodes[node];
+            node.visits += 1;
+            match outcome.winner() {
+                Some(winner) if winner == node.moved_player() => node.wins += 1,
+                Some(_) => {}
+                None => node.draws += 1,
+            }
+            curr_node = node.parent;
+        }
+    }
+
+    /// Continue with the position after the move from `position`. The subtree below the move
+    /// becomes the new tree, so its playouts are not lost. Fails if the move is illegal.
+    pub fn advance(&mut self, position: usize) -> Result<(), GameError> {
+        let child = self.nodes[0]
+            .children
+            .iter()
+            .copied()
+            .find(|child| self.nodes[*child].move_position == position);
+        match child {
+            Some(child) => self.reroot(child),
+            None => {
+                let state = self.nodes[0].state.make_move(position)?;
+                self.nodes = vec![Node::new(state, 0, None)];
+            }
+        }
+        Ok(())
+    }
+
+    /// Keep only the subtree below `root`, renumbered with `root` at index 0.
+    fn reroot(&mut self, root: usize) {
+        let mut old_nodes: Vec<Option<Node>> = std::mem::take(&mut self.nodes)
+            .into_iter()
+            .map(Some)
+            .collect();
+        // Nodes in the order of the new tree, a node gets its index in this list
+        let mut order = vec![(root, None)];
+        while self.nodes.len() < order.len() {
+            let (old_index, parent) = order[self.nodes.len()];
+            let mut node = old_nodes[old_index].take().expect("Nodes form a tree.");
+            let index = self.nodes.len();
+            let first_child = order.len();
+            order.extend(node.children.iter().map(|child| (*child, Some(index))));
+            node.children = (first_child..order.len()).collect();
+            node.parent = parent;
+            self.nodes.push(node);
+        }
+    }
+
+    /// The position from which the moves are searched.
+    pub fn root_state(&self) -> &GameState {
+        &self.nodes[0].state
+    }
+
+    /// Number of nodes of the tree.
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The most visited move from the root or `None` if no move was explored.
+    pub fn best_move(&self) -> Option<usize> {
+        self.nodes[0]
+            .children
+            .iter()
+            .max_by_key(|child| self.nodes[**child].visits)
+            .map(|child| self.nodes[*child].move_position)
+    }
+
+    /// Results of the playouts for every move from the root (same format as the result of
+    /// `game_playout`).
+    pub fn root_statistics(&self) -> Vec<PlayoutCounts> {
+        let mut counts =
+            vec![PlayoutCounts::default(); self.nodes[0].state.rules.number_of_cells()];
+        for child in self.nodes[0].children.iter() {
+            let child = &self.nodes[*child];
+            let moved_player = child.moved_player() as usize;
+            let child_counts = &mut counts[child.move_position];
+            child_counts.wins[moved_player] = child.wins;
+            child_counts.wins[1 - moved_player] = child.visits - child.wins - child.draws;
+            child_counts.draws = child.draws;
+        }
+        counts
+    }
+
+    /// Statistics of the moves from the root for the player who is to move there. The most
+    /// visited move is recommended.
+    pub fn evaluation(&self) -> Evaluation {
+        Evaluation::from_playout_counts(
+            self.nodes[0].state.curr_player,
+            &self.root_statistics(),
+            self.best_move(),
+        )
+    }
+}
+
+/// Run a tree search from `state` and return the evaluation of the moves from `state`. The
+/// random numbers are not reproducible, see `tree_search_with_policy`.
+pub fn tree_search(
+    state: GameState,
+    exploration_constant: f64,
+    budget: &SearchBudget,
+) -> Result<Evaluation, GameError> {
+    tree_search_with_policy(
+        state,
+        exploration_constant,
+        Rc::new(UniformRandom),
+        &mut Rng::from_entropy(),
+        budget,
+    )
+}
+
+/// Same as `tree_search`, but the moves of the playouts are chosen by `playout_policy` and the
+/// random numbers are taken from `rng`.
+pub fn tree_search_with_policy(
+    state: GameState,
+    exploration_constant: f64,
+    playout_policy: Rc<dyn PlayoutPolicy>,
+    rng: &mut Rng,
+    budget: &SearchBudget,
+) -> Result<Evaluation, GameError> {
+    if state.outcome().is_some() {
+        return Err(GameError::NoMoveFound);
+    }
+    let mut search =
+        MonteCarloTreeSearch::with_policy(state, exploration_constant, playout_policy, rng.split());
+    search.run(budget);
+    Ok(search.evaluation())
+}
@@ -1,180 +1,523 @@
-use crate::{GameState, GameError, LENGTH_OF_FIELD};
+pub mod alphabeta;
+pub mod book;
+pub mod budget;
+pub mod difficulty;
+pub mod evaluation;
+pub mod evaluator;
+pub mod mcts;
+pub mod playout;
+pub mod rng;
+pub mod search;
+pub mod tablebase;
+
+use crate::history::PositionHistory;
+use crate::{GameError, GameState, Outcome};
+use instant::Instant;
+use std::cell::RefCell;
+use std::rc::Rc;
 use wasm_bindgen::JsValue;
-use std::time::Instant;
-use rand::Rng;
 
 use wasm_bindgen::prelude::*;
 
-pub fn get_random_number(from: usize, to: usize) -> usize {
-    #[cfg(not(target_arch = "wasm32"))]
-        {
-            let mut rng = rand::thread_rng();
-            return rng.gen_range(from, to);
-        };
+use self::alphabeta::{AlphaBetaSearch, DEFAULT_MAX_DEPTH};
+use self::book::OpeningBook;
+use self::budget::SearchBudget;
+use self::difficulty::{Difficulty, MoveChoice};
+use self::evaluation::{Evaluation, PlayoutCounts};
+use self::evaluator::{Evaluator, EvaluatorKind, StoneDifference};
+use self::mcts::{MonteCarloTreeSearch, DEFAULT_EXPLORATION_CONSTANT};
+use self::playout::{PlayoutPolicy, PlayoutPolicyKind, UniformRandom};
+use self::rng::Rng;
+use self::search::{Search, SearchKind};
+use self::tablebase::Tablebase;
 
-    #[cfg(target_arch = "wasm32")]
-        {
-            return (js_sys::Math::random() * (to as f64)) as usize + from;
-        }
+/// Search algorithm used by the AI.
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Algorithm {
+    /// Random playouts for every possible move (see `game_playout`).
+    FlatMonteCarlo,
+    /// UCT based Monte Carlo tree search (see `mcts::MonteCarloTreeSearch`).
+    MonteCarloTreeSearch,
+    /// Deterministic negamax search with alpha-beta pruning (see `alphabeta::AlphaBetaSearch`).
+    AlphaBeta,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 3] = [
+        Algorithm::FlatMonteCarlo,
+        Algorithm::MonteCarloTreeSearch,
+        Algorithm::AlphaBeta,
+    ];
 }
 
 #[wasm_bindgen]
 pub struct AI {
+    algorithm: Algorithm,
+    /// Exploration constant of the UCT formula used by the tree search.
+    exploration_constant: f64,
+    /// Maximal depth in plies of the alpha-beta search.
+    max_depth: u32,
+    /// Evaluation of the leaves of the alpha-beta search.
+    evaluator: Rc<dyn Evaluator>,
+    /// Chooses the moves of the playouts of the Monte Carlo searches.
+    playout_policy: Rc<dyn PlayoutPolicy>,
+    /// Random numbers of all searches, advanced by every search.
+    rng: RefCell<Rng>,
+    /// Keep the last search and continue it in the next position (see `Search::advance_to`).
+    reuse_search: bool,
+    /// The last search of `evaluate_state_with_budget` if it is kept.
+    last_search: RefCell<Option<Search>>,
+    /// Budget of `choose_move`.
+    budget: SearchBudget,
+    /// Noise in the choice of the move by `choose_move`.
+    move_choice: MoveChoice,
+    /// Perfect moves in the endgame for `choose_move`.
+    tablebase: Option<Rc<Tablebase>>,
+    /// Moves of the opening for `choose_move`.
+    opening_book: Option<Rc<OpeningBook>>,
+}
 
+impl Default for AI {
+    fn default() -> Self {
+        AI {
+            algorithm: Algorithm::MonteCarloTreeSearch,
+            exploration_constant: DEFAULT_EXPLORATION_CONSTANT,
+            max_depth: DEFAULT_MAX_DEPTH,
+            evaluator: Rc::new(StoneDifference),
+            playout_policy: Rc::new(UniformRandom),
+            rng: RefCell::new(Rng::from_entropy()),
+            reuse_search: true,
+            last_search: RefCell::new(None),
+            budget: SearchBudget::default(),
+            move_choice: MoveChoice::default(),
+            tablebase: None,
+            opening_book: None,
+        }
+    }
 }
 
 #[wasm_bindgen]
 impl AI {
     #[wasm_bindgen(constructor)]
     pub fn new() -> AI {
-        return AI {};
+        AI::default()
+    }
+
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) {
+        self.algorithm = algorithm;
+        self.forget_search();
+    }
+
+    /// Larger values make the tree search explore less visited moves more often.
+    pub fn set_exploration_constant(&mut self, exploration_constant: f64) {
+        self.exploration_constant = exploration_constant;
+        self.forget_search();
+    }
+
+    /// Depth in plies up to which the alpha-beta search looks ahead (at least 1).
+    pub fn set_max_depth(&mut self, max_depth: u32) {
+        self.max_depth = max_depth;
+        self.forget_search();
+    }
+
+    /// Use one of the built-in evaluators for the alpha-beta search.
+    pub fn set_evaluator_kind(&mut self, kind: EvaluatorKind) {
+        self.evaluator = Rc::from(kind.evaluator());
+        self.forget_search();
+    }
+
+    /// Use one of the built-in playout policies for the Monte Carlo searches.
+    pub fn set_playout_policy_kind(&mut self, kind: PlayoutPolicyKind) {
+        self.playout_policy = kind.policy();
+        self.forget_search();
+    }
+
+    /// Restart the random numbers with `seed`. An AI with the same settings and seed evaluates
+    /// the same positions in the same way (as long as the budget does not limit the time).
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = RefCell::new(Rng::new(seed));
+        self.forget_search();
+    }
+
+    /// Keep the search of `evaluate_state_with_budget` and continue it if the next position can
+    /// be reached from the last one within two plies. Enabled by default. The tree search keeps
+    /// the subtree of the moves played, the alpha-beta search its transposition table.
+    pub fn set_reuse_search(&mut self, reuse_search: bool) {
+        self.reuse_search = reuse_search;
+        self.forget_search();
+    }
+
+    /// Play at the given level: sets the algorithm, the playout policy, the budget and the
+    /// noise of `choose_move`.
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        let settings = difficulty.settings();
+        self.set_algorithm(settings.algorithm);
+        self.set_playout_policy_kind(settings.playout_policy);
+        self.budget = settings.budget;
+        self.move_choice = settings.move_choice;
+    }
+
+    /// Budget of every move chosen by `choose_move`.
+    pub fn set_budget(&mut self, budget: &SearchBudget) {
+        self.budget = *budget;
+    }
+
+    pub fn budget(&self) -> SearchBudget {
+        self.budget
+    }
+
+    /// Noise in the choice of the move, see `difficulty::MoveChoice`. Both 0 (the default)
+    /// always plays the recommended move.
+    pub fn set_move_noise(&mut self, temperature: f64, blunder_probability: f64) {
+        self.move_choice = MoveChoice {
+            temperature,
+            blunder_probability,
+        };
+    }
+
+    /// Move to play after a search with the given evaluation, taking the noise of the
+    /// difficulty into account. `undefined` if the evaluation contains no possible move.
+    pub fn select_move(&self, evaluation: &Evaluation) -> Option<usize> {
+        self.move_choice
+            .choose(evaluation, &mut self.rng.borrow_mut())
+    }
+
+    /// Play the positions of `tablebase` perfectly in `choose_move` instead of searching them.
+    pub fn set_tablebase_wasm(&mut self, tablebase: Tablebase) {
+        self.set_tablebase(Some(Rc::new(tablebase)));
+    }
+
+    /// Best move in `state` according to the tablebase of the AI, `undefined` without a
+    /// tablebase or if it does not contain the position.
+    pub fn tablebase_move(&self, state: &GameState) -> Option<usize> {
+        self.tablebase.as_ref()?.best_move(state)
+    }
+
+    /// Play the moves of `opening_book` in `choose_move` instead of searching the positions.
+    pub fn set_opening_book_wasm(&mut self, opening_book: OpeningBook) {
+        self.set_opening_book(Some(Rc::new(opening_book)));
+    }
+
+    /// One of the moves of the opening book of the AI in `state` chosen at random by the weights
+    /// of the moves, `undefined` without a book or if it contains no move in `state`.
+    pub fn book_move(&self, state: &GameState) -> Option<usize> {
+        self.opening_book
+            .as_ref()?
+            .choose(state, &mut self.rng.borrow_mut())
+    }
+
+    /// Search `state` within the budget of the AI and choose the move to play (see
+    /// `select_move`). Positions of the tablebase and the opening book are not searched, see
+    /// `set_tablebase_wasm` and `set_opening_book_wasm`.
+    pub fn choose_move(&self, state: &GameState) -> Result<usize, JsValue> {
+        self.next_move(state)
+            .map_err(|e| JsValue::from(format!("Error: {:?}", e).as_str()))
     }
 
     /// Check for best move.
     /// :num_rounds: If > 0 then calculate max num_rounds playouts for every position.
     /// :max_time_to_think_in_millis: If num_rounds < 0 and max_time_to_think_in_millis > 0 then do playout until the maximal time to think is not over.
     /// If both parameters are < 0, the default is to play 100 games.
+    /// If both parameters are 0, one playout is calculated for every position, so that every
+    /// algorithm searches at least once.
     pub fn evaluate_state_for_next_move(
         &self,
         state: &GameState,
         num_rounds: i32,
         max_time_to_think_in_millis: u32,
-    ) -> Result<*const i32, JsValue> {
-        let curr_player = state.curr_player;
-        let result = if num_rounds > 0 || max_time_to_think_in_millis <= 0 {
+    ) -> Result<Evaluation, JsValue> {
+        let budget = if num_rounds > 0 || max_time_to_think_in_millis == 0 {
             if num_rounds < 0 {
-                game_playout(state.clone(), Some(100), None) // default
+                SearchBudget::default()
             } else {
-                game_playout(state.clone(), Some(num_rounds as u32), None)
+                SearchBudget::playouts((num_rounds as u32).max(1))
             }
         } else {
-            game_playout(
-                state.clone(),
-                None,
-                Some(max_time_to_think_in_millis as u128),
-            )
+            SearchBudget::time(max_time_to_think_in_millis)
         };
-        match result {
-            Ok(wins_losses) => {
-                let wins_of_curr_player = wins_losses
-                    .iter()
-                    .map(|v| {
-                        if curr_player == 0 {
-                            v.0 as i32 - v.1 as i32
-                        } else {
-                            v.1 as i32 - v.0 as i32
-                        }
-                    })
-                    .collect::<Vec<i32>>();
-
-                Ok(wins_of_curr_player.as_ptr())
-            }
-            Err(e) => Err(JsValue::from(format!("Error: {:?}", e))),
+
+        self.evaluate_state_with_budget(state, &budget)
+    }
+
+    /// Check for best move within the given budget. The number of playouts in the budget is
+    /// counted for every possible move. The tree search uses the same total number of
+    /// playouts, i.e., the playouts of the budget times the number of possible moves. The
+    /// alpha-beta search only uses the time of the budget and stops at the maximal depth.
+    pub fn evaluate_state_with_budget(
+        &self,
+        state: &GameState,
+        budget: &SearchBudget,
+    ) -> Result<Evaluation, JsValue> {
+        self.evaluate(state, budget)
+            .map_err(|e| JsValue::from(format!("Error: {:?}", e).as_str()))
+    }
+
+    /// Think during the turn of the opponent: search from `state` (the opponent to move) within
+    /// the budget and keep the search, so that the next evaluation after the move of the
+    /// opponent continues it. Does nothing if the search is not reused.
+    pub fn ponder(&self, state: &GameState, budget: &SearchBudget) -> Result<(), JsValue> {
+        if self.reuse_search {
+            self.evaluate_state_with_budget(state, budget)?;
         }
+        Ok(())
+    }
+
+    /// Start a search from `state` which can be continued step by step (see `Search`), e.g. to
+    /// show the progress. Fails if the game is over.
+    pub fn start_search(&self, state: &GameState) -> Result<Search, JsValue> {
+        self.search(state)
+            .map_err(|e| JsValue::from(format!("Error: {:?}", e)))
     }
 }
 
-/// Plays a random game from the current game state. I.e., choose as long random moves by player
-/// 1 and 2 until the game is over. When the game is over, return the winner ID (0 or 1). If
-/// the game could not be finished (in some very seldom game states, there is no way to end the
-/// game because no move removes any additional beans.
-pub fn random_playout(state: GameState) -> Result<u8, GameError> {
-    let mut newstate: GameState = state;
-    let mut abort_counter = 100000;
-    loop {
-        assert!(
-            abort_counter > 0,
-            format!("Could not complete the playout.")
-        );
-        abort_counter -= 1;
-
-        if let Some(winner) = newstate.winner() {
-            return Ok(winner);
+impl AI {
+    /// Use a custom evaluator for the alpha-beta search.
+    pub fn set_evaluator(&mut self, evaluator: impl Evaluator + 'static) {
+        self.evaluator = Rc::new(evaluator);
+        self.forget_search();
+    }
+
+    /// Use a custom playout policy for the Monte Carlo searches.
+    pub fn set_playout_policy(&mut self, policy: impl PlayoutPolicy + 'static) {
+        self.playout_policy = Rc::new(policy);
+        self.forget_search();
+    }
+
+    /// The kept search continued in `state` or `None` if there is none for `state`.
+    fn kept_search(&self, state: &GameState) -> Option<Search> {
+        if state.outcome().is_some() {
+            return None;
+        }
+        let mut search = self.last_search.borrow_mut().take()?;
+        if search.advance_to(state) {
+            Some(search)
+        } else {
+            None
+        }
+    }
+
+    /// Drop the kept search, e.g. because the settings changed.
+    fn forget_search(&mut self) {
+        self.last_search.replace(None);
+    }
+
+    /// Same as `evaluate_state_with_budget`.
+    pub fn evaluate(
+        &self,
+        state: &GameState,
+        budget: &SearchBudget,
+    ) -> Result<Evaluation, GameError> {
+        let mut search = match self.kept_search(state) {
+            Some(search) => search,
+            None => self.search(state)?,
+        };
+        search.run(budget);
+        let evaluation = search.evaluation();
+        if self.reuse_search {
+            self.last_search.replace(Some(search));
+        }
+        Ok(evaluation)
+    }
+
+    /// Same as `set_tablebase_wasm`, the tablebase can be shared by several AIs. `None` removes
+    /// the tablebase.
+    pub fn set_tablebase(&mut self, tablebase: Option<Rc<Tablebase>>) {
+        self.tablebase = tablebase;
+    }
+
+    /// Same as `set_opening_book_wasm`, the book can be shared by several AIs. `None` removes
+    /// the book.
+    pub fn set_opening_book(&mut self, opening_book: Option<Rc<OpeningBook>>) {
+        self.opening_book = opening_book;
+    }
+
+    /// Same as `choose_move`.
+    pub fn next_move(&self, state: &GameState) -> Result<usize, GameError> {
+        if let Some(position) = self.tablebase_move(state).or_else(|| self.book_move(state)) {
+            return Ok(position);
         }
+        let evaluation = self.evaluate(state, &self.budget)?;
+        self.select_move(&evaluation).ok_or(GameError::NoMoveFound)
+    }
 
-        let field_from_which_to_make_next_step =
-            &newstate.fields_of_players[newstate.curr_player as usize];
+    /// Same as `start_search`.
+    pub fn search(&self, state: &GameState) -> Result<Search, GameError> {
+        if state.outcome().is_some() {
+            return Err(GameError::NoMoveFound);
+        }
+        let rng = self.rng.borrow_mut().split();
+        let kind = match self.algorithm {
+            Algorithm::FlatMonteCarlo => SearchKind::FlatMonteCarlo(FlatMonteCarlo::new(
+                state.clone(),
+                self.playout_policy.clone(),
+                rng,
+            )),
+            Algorithm::MonteCarloTreeSearch => {
+                SearchKind::MonteCarloTreeSearch(MonteCarloTreeSearch::with_policy(
+                    state.clone(),
+                    self.exploration_constant,
+                    self.playout_policy.clone(),
+                    rng,
+                ))
+            }
+            Algorithm::AlphaBeta => SearchKind::AlphaBeta {
+                search: AlphaBetaSearch::with_evaluator(state.clone(), self.evaluator.clone()),
+                max_depth: self.max_depth,
+            },
+        };
+        Ok(Search::new(state, kind))
+    }
+}
 
-        // Get only non-empty cells from which one can make a move.
-        let non_empty_cell_ids: Vec<usize> = field_from_which_to_make_next_step
-            .cells
-            .iter()
-            .enumerate()
-            .filter(|(_, stones_in_cell)| **stones_in_cell > 0)
-            .map(|(cell_id, _)| cell_id)
-            .collect();
+/// Maximal number of plies of a random playout. Longer playouts are scored as a draw (the draw
+/// rules of the game may be disabled).
+pub const MAX_PLAYOUT_PLIES: u32 = 100000;
 
-        assert!(non_empty_cell_ids.len() > 0);
-        // println!("{:?}", non_empty_cell_ids);
+/// Plays a random game from the current game state. I.e., choose as long random moves by player
+/// 1 and 2 until the game is over. When the game is over, return its outcome. Repeated positions
+/// are counted from `state` on, earlier positions of the game are not known.
+pub fn random_playout(state: GameState, rng: &mut Rng) -> Result<Outcome, GameError> {
+    playout_with_policy(state, &UniformRandom, rng)
+}
 
-        let random_move_id = if non_empty_cell_ids.len() > 1 {
-            get_random_number(0, non_empty_cell_ids.len())
+/// Same as `random_playout`, but the moves are chosen by `policy`.
+pub fn playout_with_policy(
+    state: GameState,
+    policy: &dyn PlayoutPolicy,
+    rng: &mut Rng,
+) -> Result<Outcome, GameError> {
+    let mut newstate: GameState = state;
+    let mut history = PositionHistory::new();
+    for _ in 0..MAX_PLAYOUT_PLIES {
+        history.push(&newstate);
+        if let Some(outcome) = history.outcome(&newstate) {
+            return Ok(outcome);
+        }
+
+        newstate = policy
+            .choose_move(&newstate, rng)
+            .ok_or(GameError::NoMoveFound)?
+            .1;
+    }
+    Ok(Outcome::Draw)
+}
+
+/// Choose one of the legal moves of the current player uniformly at random. Returns the position
+/// of the move and the new game state or `None` if there is no legal move.
+///
+/// Instead of computing all legal moves, random occupied cells are tried until one of them is a
+/// legal move.
+pub fn random_move(state: &GameState, rng: &mut Rng) -> Option<(usize, GameState)> {
+    let mut candidates: Vec<usize> = state.occupied_cells().collect();
+    while !candidates.is_empty() {
+        let candidate_id = if candidates.len() > 1 {
+            rng.gen_range(0, candidates.len())
         } else {
             0
         };
-        let newstate_result = newstate.make_move(non_empty_cell_ids[random_move_id]);
-        if newstate_result.is_ok() {
-            newstate = newstate_result.unwrap();
-        } else {
-            let error = newstate_result.err().unwrap();
-            return Err(error);
+        if let Ok(newstate) = state.make_move(candidates[candidate_id]) {
+            return Some((candidates[candidate_id], newstate));
         }
+        candidates.swap_remove(candidate_id);
     }
+    None
 }
 
-/// Make a complete random game playout. The result is the distribution of wins/losts for every
-/// position on the field.
+/// Make a complete random game playout. The result is the distribution of wins, losses and draws
+/// for every position on the field. The playouts of the budget are counted for every position, at
+/// least one playout is done for every position. The random numbers are not reproducible, see
+/// `game_playout_with_policy`.
 pub fn game_playout(
     state: GameState,
-    num_rounds: Option<u32>,
-    max_time_in_millis: Option<u128>,
-) -> Result<[(u32, u32); 2 * LENGTH_OF_FIELD], GameError> {
-    let mut wins_losses: [(u32, u32); 2 * LENGTH_OF_FIELD] = [(0, 0); 2 * LENGTH_OF_FIELD];
-    let mut rounds_counter = 0;
-
-    #[cfg(not(target_arch = "wasm32"))] let starttime = { Instant::now() };
-
-    loop {
-        for pos_on_board in 0..(2 * LENGTH_OF_FIELD) {
-            let newstate = state.make_move(pos_on_board);
-            if let Ok(newstate) = newstate {
-                if let Some(winnerid) = newstate.winner() {
-                    wins_losses[pos_on_board] = if winnerid == 0 {
-                        (wins_losses[pos_on_board].0 + 1, wins_losses[pos_on_board].1)
-                    } else {
-                        (wins_losses[pos_on_board].0, wins_losses[pos_on_board].1 + 1)
-                    };
-                } else {
-                    let winner = random_playout(newstate.clone());
-                    if let Ok(winnerid) = winner {
-                        wins_losses[pos_on_board] = if winnerid == 0 {
-                            (wins_losses[pos_on_board].0 + 1, wins_losses[pos_on_board].1)
-                        } else {
-                            (wins_losses[pos_on_board].0, wins_losses[pos_on_board].1 + 1)
-                        };
-                    }
+    budget: &SearchBudget,
+) -> Result<Vec<PlayoutCounts>, GameError> {
+    game_playout_with_policy(
+        state,
+        Rc::new(UniformRandom),
+        &mut Rng::from_entropy(),
+        budget,
+    )
+}
+
+/// Same as `game_playout`, but the moves of the playouts are chosen by `policy` with random
+/// numbers seeded by `rng`.
+pub fn game_playout_with_policy(
+    state: GameState,
+    policy: Rc<dyn PlayoutPolicy>,
+    rng: &mut Rng,
+    budget: &SearchBudget,
+) -> Result<Vec<PlayoutCounts>, GameError> {
+    let mut search = FlatMonteCarlo::new(state, policy, rng.split());
+    search.run(budget);
+    Ok(search.counts().to_vec())
+}
+
+/// Flat Monte Carlo search: the same number of playouts after every possible move. The search
+/// can be continued by further calls of `run`.
+pub struct FlatMonteCarlo {
+    state: GameState,
+    legal_moves: Vec<(usize, GameState)>,
+    counts: Vec<PlayoutCounts>,
+    policy: Rc<dyn PlayoutPolicy>,
+    rng: Rng,
+}
+
+impl FlatMonteCarlo {
+    pub fn new(state: GameState, policy: Rc<dyn PlayoutPolicy>, rng: Rng) -> Self {
+        FlatMonteCarlo {
+            legal_moves: state.legal_moves().collect(),
+            counts: vec![PlayoutCounts::default(); state.rules.number_of_cells()],
+            state,
+            policy,
+            rng,
+        }
+    }
+
+    /// Do rounds of one playout per possible move until the budget is exhausted, at least one
+    /// round is done. Returns the number of done rounds.
+    pub fn run(&mut self, budget: &SearchBudget) -> u32 {
+        let mut rounds_counter = 0;
+
+        let starttime = Instant::now();
+
+        loop {
+            for (pos_on_board, newstate) in self.legal_moves.iter() {
+                if let Ok(outcome) =
+                    playout_with_policy(newstate.clone(), self.policy.as_ref(), &mut self.rng)
+                {
+                    self.counts[*pos_on_board].record(outcome);
                 }
             }
-        }
 
-        rounds_counter += 1;
+            rounds_counter += 1;
 
-        // ----- START: Stop conditions --------
-        if let Some(num_rounds) = num_rounds {
-            if num_rounds < rounds_counter {
-                return Ok(wins_losses);
+            // ----- START: Stop conditions --------
+            if budget.is_exhausted(rounds_counter, &starttime) {
+                return rounds_counter;
             }
+            // ----- END: Stop conditions --------
         }
+    }
 
-        #[cfg(not(target_arch = "wasm32"))]
-            {
-                if let Some(max_time_in_millis) = max_time_in_millis {
-                    if starttime.elapsed().as_millis() > max_time_in_millis {
-                        return Ok(wins_losses);
-                    }
-                }
-            }
-        // ----- END: Stop conditions --------
+    /// Continue with the position after the move from `position`. The playouts of the old
+    /// position are of no use there, so the counts start again. Fails if the move is illegal.
+    pub fn advance(&mut self, position: usize) -> Result<(), GameError> {
+        let state = self.state.make_move(position)?;
+        self.legal_moves = state.legal_moves().collect();
+        self.counts = vec![PlayoutCounts::default(); state.rules.number_of_cells()];
+        self.state = state;
+        Ok(())
     }
-}
 
+    /// Results of the playouts for every position on the field.
+    pub fn counts(&self) -> &[PlayoutCounts] {
+        &self.counts
+    }
+
+    /// Statistics of the moves for the player who is to move. The move with the highest
+    /// difference of wins and losses is recommended.
+    pub fn evaluation(&self) -> Evaluation {
+        Evaluation::from_playout_counts(self.state.curr_player, &self.counts, None)
+    }
+}
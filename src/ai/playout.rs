@@ -0,0 +1,145 @@
+use crate::ai::evaluator::{Evaluator, StoneDifference};
+use crate::ai::random_move;
+use crate::ai::rng::Rng;
+use crate::GameState;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+/// Chooses the moves of the playouts (see `ai::playout_with_policy`).
+pub trait PlayoutPolicy {
+    /// Next move of the current player: the position of the move and the new game state or
+    /// `None` if there is no legal move. All random decisions use `rng`.
+    fn choose_move(&self, state: &GameState, rng: &mut Rng) -> Option<(usize, GameState)>;
+}
+
+/// Every legal move with the same probability (see `ai::random_move`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UniformRandom;
+
+impl PlayoutPolicy for UniformRandom {
+    fn choose_move(&self, state: &GameState, rng: &mut Rng) -> Option<(usize, GameState)> {
+        random_move(state, rng)
+    }
+}
+
+/// Makes the move which captures the most stones, chosen at random among equal captures. With
+/// probability `epsilon` (and if no move captures anything) a uniformly random move is made.
+#[derive(Debug, Clone, Copy)]
+pub struct GreedyCapture {
+    pub epsilon: f64,
+}
+
+impl Default for GreedyCapture {
+    fn default() -> Self {
+        GreedyCapture { epsilon: 0.1 }
+    }
+}
+
+impl PlayoutPolicy for GreedyCapture {
+    fn choose_move(&self, state: &GameState, rng: &mut Rng) -> Option<(usize, GameState)> {
+        if rng.gen_fraction() < self.epsilon {
+            return random_move(state, rng);
+        }
+        let mut best_moves: Vec<(usize, GameState)> = vec![];
+        let mut best_capture = 1;
+        for (position, new_state) in state.legal_moves() {
+            let capture = new_state.move_statistic.stones_of_opponent_removed;
+            if capture > best_capture {
+                best_capture = capture;
+                best_moves.clear();
+            }
+            if capture == best_capture {
+                best_moves.push((position, new_state));
+            }
+        }
+        if best_moves.is_empty() {
+            return random_move(state, rng);
+        }
+        let index = rng.gen_range(0, best_moves.len());
+        Some(best_moves.swap_remove(index))
+    }
+}
+
+/// Chooses a move with a probability proportional to `exp(value / temperature)`, where `value`
+/// is the value of the position after the move for the moving player. Lower temperatures make
+/// the better moves more likely.
+pub struct Softmax {
+    pub evaluator: Rc<dyn Evaluator>,
+    temperature: f32,
+}
+
+impl Softmax {
+    /// Softmax policy with the given evaluator and temperature. Returns `None` if the temperature
+    /// is not a positive number (the weights of the moves would not be defined).
+    pub fn new(evaluator: impl Evaluator + 'static, temperature: f32) -> Option<Self> {
+        if !(temperature.is_finite() && temperature > 0.0) {
+            return None;
+        }
+        Some(Softmax {
+            evaluator: Rc::new(evaluator),
+            temperature,
+        })
+    }
+
+    pub fn temperature(&self) -> f32 {
+        self.temperature
+    }
+}
+
+impl Default for Softmax {
+    fn default() -> Self {
+        Softmax {
+            evaluator: Rc::new(StoneDifference),
+            temperature: 1.0,
+        }
+    }
+}
+
+impl PlayoutPolicy for Softmax {
+    fn choose_move(&self, state: &GameState, rng: &mut Rng) -> Option<(usize, GameState)> {
+        let mut moves: Vec<(usize, GameState)> = state.legal_moves().collect();
+        let values: Vec<f32> = moves
+            .iter()
+            .map(|(_, new_state)| self.evaluator.evaluate(new_state, state.curr_player))
+            .collect();
+        let max_value = values.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let weights: Vec<f64> = values
+            .iter()
+            .map(|value| (((value - max_value) / self.temperature) as f64).exp())
+            .collect();
+
+        let mut remaining = rng.gen_fraction() * weights.iter().sum::<f64>();
+        for (index, weight) in weights.iter().enumerate() {
+            remaining -= weight;
+            if remaining < 0.0 {
+                return Some(moves.swap_remove(index));
+            }
+        }
+        moves.pop()
+    }
+}
+
+/// The built-in playout policies, e.g. to choose one in the UI.
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PlayoutPolicyKind {
+    UniformRandom,
+    GreedyCapture,
+    Softmax,
+}
+
+impl PlayoutPolicyKind {
+    pub const ALL: [PlayoutPolicyKind; 3] = [
+        PlayoutPolicyKind::UniformRandom,
+        PlayoutPolicyKind::GreedyCapture,
+        PlayoutPolicyKind::Softmax,
+    ];
+
+    pub fn policy(self) -> Rc<dyn PlayoutPolicy> {
+        match self {
+            PlayoutPolicyKind::UniformRandom => Rc::new(UniformRandom),
+            PlayoutPolicyKind::GreedyCapture => Rc::new(GreedyCapture::default()),
+            PlayoutPolicyKind::Softmax => Rc::new(Softmax::default()),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use wasm_bindgen::prelude::*;
+
+/// Pseudo random number generator of the AI (xoshiro256**, seeded with SplitMix64).
+///
+/// Only integer arithmetic is used, so a seed gives the same sequence of numbers on native
+/// targets and in the browser. This makes games of the AI reproducible.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+/// Next value of the SplitMix64 sequence starting at `seed`.
+fn split_mix(seed: &mut u64) -> u64 {
+    *seed = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *seed;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[wasm_bindgen]
+impl Rng {
+    /// Generator which always produces the same numbers for the same seed.
+    #[wasm_bindgen(constructor)]
+    pub fn new(seed: u64) -> Rng {
+        let mut seed = seed;
+        Rng {
+            state: [
+                split_mix(&mut seed),
+                split_mix(&mut seed),
+                split_mix(&mut seed),
+                split_mix(&mut seed),
+            ],
+        }
+    }
+
+    /// Generator with a seed which differs every time.
+    pub fn from_entropy() -> Rng {
+        Rng::new(entropy())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+        result
+    }
+
+    /// Random number in `[from, to)`. Returns `from` if the range is empty.
+    pub fn gen_range(&mut self, from: usize, to: usize) -> usize {
+        if to <= from {
+            return from;
+        }
+        let range = (to - from) as u128;
+        from + ((self.next_u64() as u128 * range) >> 64) as usize
+    }
+
+    /// Random number in `[0, 1)`.
+    pub fn gen_fraction(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// New generator seeded by this one, e.g. for a search which owns its generator.
+    pub fn split(&mut self) -> Rng {
+        Rng::new(self.next_u64())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn entropy() -> u64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    RandomState::new().build_hasher().finish()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn entropy() -> u64 {
+    let high = (js_sys::Math::random() * 4294967296.0) as u64;
+    let low = (js_sys::Math::random() * 4294967296.0) as u64;
+    (high << 32) | low
+}
@@ -0,0 +1,180 @@
+use crate::ai::alphabeta::AlphaBetaSearch;
+use crate::ai::budget::SearchBudget;
+use crate::ai::evaluation::Evaluation;
+use crate::ai::mcts::MonteCarloTreeSearch;
+use crate::ai::{Algorithm, FlatMonteCarlo};
+use crate::{GameError, GameState};
+use wasm_bindgen::prelude::*;
+
+/// The search algorithm of a `Search` together with its state.
+pub enum SearchKind {
+    FlatMonteCarlo(FlatMonteCarlo),
+    MonteCarloTreeSearch(MonteCarloTreeSearch),
+    AlphaBeta {
+        search: AlphaBetaSearch,
+        max_depth: u32,
+    },
+}
+
+/// A search from a position which can be continued step by step (see `AI::start_search`).
+///
+/// Between the steps, the best move and the statistics can be queried, e.g. to show the
+/// progress in the UI. The state of the search (e.g. the tree of the tree search) is kept, so
+/// the steps together give the same result as one search with the sum of their budgets.
+///
+/// After a move, the search can continue from the new position (see `advance`), keeping what
+/// was found there, e.g. the subtree of the tree search. This allows to think ahead during the
+/// turn of the opponent (pondering).
+#[wasm_bindgen]
+pub struct Search {
+    kind: SearchKind,
+    state: GameState,
+    number_of_moves: u32,
+}
+
+impl Search {
+    pub fn new(state: &GameState, kind: SearchKind) -> Self {
+        Search {
+            kind,
+            state: state.clone(),
+            number_of_moves: state.legal_moves().count() as u32,
+        }
+    }
+
+    /// Continue the search in the position after the move from `position`. Fails if the move
+    /// is illegal.
+    pub fn advance(&mut self, position: usize) -> Result<(), GameError> {
+        if self.state.outcome().is_some() {
+            return Err(GameError::NoMoveFound);
+        }
+        match &mut self.kind {
+            SearchKind::FlatMonteCarlo(search) => search.advance(position)?,
+            SearchKind::MonteCarloTreeSearch(search) => search.advance(position)?,
+            SearchKind::AlphaBeta { search, .. } => search.advance(position)?,
+        }
+        self.state = self.state.make_move(position)?;
+        self.number_of_moves = self.state.legal_moves().count() as u32;
+        Ok(())
+    }
+
+    /// Continue the search until the budget is exhausted. The playouts of the budget are counted
+    /// per possible move. The alpha-beta search only uses the time of the budget and deepens
+    /// up to its maximal depth.
+    pub fn run(&mut self, budget: &SearchBudget) {
+        match &mut self.kind {
+            SearchKind::FlatMonteCarlo(search) => {
+                search.run(budget);
+            }
+            SearchKind::MonteCarloTreeSearch(search) => {
+                search.run(&budget.scale_playouts(self.number_of_moves));
+            }
+            SearchKind::AlphaBeta { search, max_depth } => {
+                search.run(*max_depth, budget);
+            }
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl Search {
+    /// Continue the search with `playouts` playouts per possible move. The alpha-beta search
+    /// searches one ply deeper instead.
+    pub fn step(&mut self, playouts: u32) {
+        match &mut self.kind {
+            SearchKind::AlphaBeta { search, max_depth } => {
+                let depth = (search.completed_depth() + 1).min(*max_depth);
+                search.run(depth, &SearchBudget::new(None, None));
+            }
+            _ => self.run(&SearchBudget::playouts(playouts.max(1))),
+        }
+    }
+
+    /// Continue the search for `millis` milliseconds. The alpha-beta search only completes
+    /// the depths which fit into the time (at least one ply is always searched).
+    pub fn step_for(&mut self, millis: u32) {
+        self.run(&SearchBudget::time(millis));
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        match &self.kind {
+            SearchKind::FlatMonteCarlo(_) => Algorithm::FlatMonteCarlo,
+            SearchKind::MonteCarloTreeSearch(_) => Algorithm::MonteCarloTreeSearch,
+            SearchKind::AlphaBeta { .. } => Algorithm::AlphaBeta,
+        }
+    }
+
+    /// True if more steps do not change the result, i.e., the alpha-beta search reached its
+    /// maximal depth. The Monte Carlo searches can always be continued.
+    pub fn is_finished(&self) -> bool {
+        match &self.kind {
+            SearchKind::AlphaBeta { search, max_depth } => {
+                search.completed_depth() >= (*max_depth).max(1)
+            }
+            _ => false,
+        }
+    }
+
+    /// The currently recommended move or `None` if nothing was searched yet.
+    pub fn best_move(&self) -> Option<usize> {
+        self.evaluation().recommended_move()
+    }
+
+    /// Statistics of the moves found so far.
+    pub fn evaluation(&self) -> Evaluation {
+        match &self.kind {
+            SearchKind::FlatMonteCarlo(search) => search.evaluation(),
+            SearchKind::MonteCarloTreeSearch(search) => search.evaluation(),
+            SearchKind::AlphaBeta { search, .. } => search.evaluation(),
+        }
+    }
+
+    /// Continue the search in `state` if it is the position of the search or can be reached
+    /// from it by up to two plies (e.g. a move of the AI and the answer of the opponent).
+    /// Returns false if `state` cannot be reached, then the search is unchanged.
+    pub fn advance_to(&mut self, state: &GameState) -> bool {
+        if is_same_position(&self.state, state) {
+            return true;
+        }
+        for (position, child) in self.state.legal_moves() {
+            if is_same_position(&child, state) {
+                return self.advance(position).is_ok();
+            }
+            for (reply, grandchild) in child.legal_moves() {
+                if is_same_position(&grandchild, state) {
+                    return self.advance(position).is_ok() && self.advance(reply).is_ok();
+                }
+            }
+        }
+        false
+    }
+
+    /// Continue the search in the position after the move from `position`.
+    pub fn advance_wasm(&mut self, position: usize) -> Result<(), JsValue> {
+        self.advance(position)
+            .map_err(|e| JsValue::from(format!("Error while doing a move: {:?}", e).as_str()))
+    }
+
+    /// The position from which the moves are searched.
+    pub fn state(&self) -> GameState {
+        self.state.clone()
+    }
+
+    /// Stop the search and return its final statistics.
+    pub fn finish(self) -> Evaluation {
+        self.evaluation()
+    }
+}
+
+/// True if both states have the same rules, cells, player to move and plies without capture.
+/// The hash is only compared first, since different positions may have the same hash.
+fn is_same_position(state: &GameState, other: &GameState) -> bool {
+    state.hash() == other.hash()
+        && state.rules() == other.rules()
+        && state.curr_player == other.curr_player
+        && state.plies_without_capture() == other.plies_without_capture()
+        && state
+            .fields_of_players
+            .iter()
+            .zip(other.fields_of_players.iter())
+            .all(|(field, other_field)| field.cells == other_field.cells)
+}
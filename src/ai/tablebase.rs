@@ -0,0 +1,495 @@
+//! Endgame tablebase: the perfect result of every position with at most a few stones per
+//! player, computed by retrograde analysis.
+//!
+//! The positions of a tablebase are all positions with 1 to `max_stones` stones per player on
+//! a board of a given length, with either player to move. Stones are never added to a field, so
+//! every move from such a position leads to another one of them or ends the game. The
+//! generation starts from the positions with a move which captures the last stones of the
+//! opponent and works backwards through the moves: a position is won if a move leads to a lost
+//! position and lost if all moves lead to won positions. Positions which are never decided this
+//! way are drawn, the players can avoid losing forever.
+//!
+//! The results are stored with the distance to the end of the game in plies, assuming that the
+//! winner ends the game as fast and the loser as late as possible. The draw rules (repetitions
+//! and plies without capture) are ignored: a player who follows the distances never repeats a
+//! position, but a very long win may still be drawn by the limit of plies without capture.
+//!
+//! A tablebase is stored as a compact file (see `to_bytes`): a small header and two bytes per
+//! position, the positions are not stored but given by their index (see `Indexer`).
+
+use crate::zobrist;
+use crate::{Direction, GameState, MoveStatistic, OnePlayersField, Outcome, RuleSet};
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use wasm_bindgen::prelude::*;
+
+/// First bytes of a tablebase file.
+pub const TABLEBASE_MAGIC: &[u8; 4] = b"BOAT";
+
+/// Version of the file format written by `to_bytes`.
+const FORMAT_VERSION: u8 = 1;
+
+/// Length of the header: magic, version, length of the field and maximal stones per player.
+const HEADER_LENGTH: usize = TABLEBASE_MAGIC.len() + 3;
+
+/// Largest supported number of stones per player, the tables grow very fast with it.
+pub const MAX_TABLEBASE_STONES: u32 = 6;
+
+/// Largest supported number of positions, e.g. 4 stones on the standard board (about 47 million
+/// positions). The generation needs about 50 bytes per position. With at most
+/// `2 * MAX_LENGTH_OF_FIELD` moves per position, the indices of all positions and moves fit
+/// into `u32`.
+pub const MAX_TABLEBASE_POSITIONS: usize = 50_000_000;
+
+/// Result of a position for the player to move.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TablebaseResult {
+    /// The player to move wins in the given number of plies.
+    Win(u16),
+    /// The player to move loses in the given number of plies.
+    Loss(u16),
+    Draw,
+}
+
+impl TablebaseResult {
+    /// Entry of the result in the table: 0 for a draw, otherwise twice the distance plus 1 for
+    /// a win.
+    fn encode(self) -> u16 {
+        match self {
+            TablebaseResult::Win(distance) => 2 * distance + 1,
+            TablebaseResult::Loss(distance) => 2 * distance,
+            TablebaseResult::Draw => 0,
+        }
+    }
+
+    fn decode(entry: u16) -> Self {
+        match entry {
+            0 => TablebaseResult::Draw,
+            _ if entry % 2 == 1 => TablebaseResult::Win(entry / 2),
+            _ => TablebaseResult::Loss(entry / 2),
+        }
+    }
+}
+
+/// Errors while reading a tablebase.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TablebaseError {
+    /// The data does not start with `TABLEBASE_MAGIC`.
+    NotATablebase,
+    UnsupportedVersion(u8),
+    InvalidLengthOfField(usize),
+    InvalidMaxStones(u32),
+    /// The number of bytes does not fit to the header.
+    WrongSize {
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for TablebaseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            TablebaseError::NotATablebase => write!(f, "the data is not a tablebase"),
+            TablebaseError::UnsupportedVersion(version) => {
+                write!(f, "unsupported version {} of the tablebase", version)
+            }
+            TablebaseError::InvalidLengthOfField(length) => {
+                write!(f, "invalid length of the field {}", length)
+            }
+            TablebaseError::InvalidMaxStones(stones) => {
+                write!(f, "invalid maximal number of stones {}", stones)
+            }
+            TablebaseError::WrongSize { expected, found } => {
+                write!(f, "expected {} bytes, found {}", expected, found)
+            }
+        }
+    }
+}
+
+/// Numbers the distributions of 1 to `max_stones` stones over the cells of a player: first all
+/// distributions of 1 stone, then of 2 stones and so on. The distributions of the same number
+/// of stones are ordered by the combinatorial number system of the cells of their stones.
+#[derive(Debug, Clone)]
+struct Indexer {
+    number_of_cells: usize,
+    max_stones: u32,
+    /// `binomials[n][k]` is n choose k.
+    binomials: Vec<Vec<u32>>,
+    /// Index of the first distribution of `s + 1` stones.
+    offsets: Vec<u32>,
+    /// All distributions by their index.
+    distributions: Vec<Vec<u8>>,
+}
+
+impl Indexer {
+    fn new(number_of_cells: usize, max_stones: u32) -> Self {
+        let size = number_of_cells + max_stones as usize;
+        let mut binomials = vec![vec![0u32; size + 1]; size + 1];
+        for n in 0..=size {
+            binomials[n][0] = 1;
+            for k in 1..=n {
+                binomials[n][k] = binomials[n - 1][k - 1] + binomials[n - 1][k];
+            }
+        }
+        let mut offsets = vec![0];
+        for stones in 1..=max_stones as usize {
+            let count = binomials[number_of_cells + stones - 1][stones];
+            offsets.push(offsets[stones - 1] + count);
+        }
+
+        let mut indexer = Indexer {
+            number_of_cells,
+            max_stones,
+            binomials,
+            offsets,
+            distributions: vec![],
+        };
+        let mut distributions = vec![vec![]; indexer.len()];
+        let mut cells = vec![0u8; number_of_cells];
+        indexer.collect_distributions(&mut cells, 0, 0, &mut distributions);
+        indexer.distributions = distributions;
+        indexer
+    }
+
+    /// Number of distributions.
+    fn len(&self) -> usize {
+        self.offsets[self.max_stones as usize] as usize
+    }
+
+    /// Number of positions of a tablebase with these distributions for both players (with
+    /// either player to move) or `None` if there are more than `MAX_TABLEBASE_POSITIONS`.
+    fn number_of_positions(&self) -> Option<usize> {
+        self.len()
+            .checked_mul(self.len())
+            .and_then(|positions| positions.checked_mul(2))
+            .filter(|positions| *positions <= MAX_TABLEBASE_POSITIONS)
+    }
+
+    /// Adds all distributions which only differ from `cells` from the cell `from` on.
+    fn collect_distributions(
+        &self,
+        cells: &mut Vec<u8>,
+        from: usize,
+        stones: u32,
+        distributions: &mut Vec<Vec<u8>>,
+    ) {
+        if from == self.number_of_cells {
+            if stones > 0 {
+                let index = self.index(cells).expect("Valid distribution.");
+                distributions[index as usize] = cells.clone();
+            }
+            return;
+        }
+        for stones_in_cell in 0..=(self.max_stones - stones) {
+            cells[from] = stones_in_cell as u8;
+            self.collect_distributions(cells, from + 1, stones + stones_in_cell, distributions);
+        }
+        cells[from] = 0;
+    }
+
+    /// Index of a distribution or `None` if it has no stones or too many.
+    fn index(&self, cells: &[u8]) -> Option<u32> {
+        let stones: u32 = cells.iter().map(|stones| *stones as u32).sum();
+        if stones == 0 || stones > self.max_stones {
+            return None;
+        }
+        // The stones in the order of their cells, the i-th stone is at `cell + i` in the
+        // combination
+        let mut index = self.offsets[stones as usize - 1];
+        let mut placed = 0;
+        for (cell, stones_in_cell) in cells.iter().enumerate() {
+            for _ in 0..*stones_in_cell {
+                index += self.binomials[cell + placed][placed + 1];
+                placed += 1;
+            }
+        }
+        Some(index)
+    }
+}
+
+/// Results of all positions up to a number of stones per player, see the module documentation.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct Tablebase {
+    length_of_field: usize,
+    indexer: Indexer,
+    /// Encoded results (see `TablebaseResult::encode`) by the index of the position.
+    entries: Vec<u16>,
+}
+
+#[wasm_bindgen]
+impl Tablebase {
+    /// Reads a tablebase written by `to_bytes`, e.g. fetched from the server.
+    pub fn from_bytes_wasm(bytes: &[u8]) -> Result<Tablebase, JsValue> {
+        Tablebase::from_bytes(bytes)
+            .map_err(|e| JsValue::from(format!("Invalid tablebase: {}", e).as_str()))
+    }
+
+    /// The tablebase as file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + 2 * self.entries.len());
+        bytes.extend_from_slice(TABLEBASE_MAGIC);
+        bytes.push(FORMAT_VERSION);
+        bytes.push(self.length_of_field as u8);
+        bytes.push(self.indexer.max_stones as u8);
+        for entry in self.entries.iter() {
+            bytes.extend_from_slice(&entry.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length_of_field(&self) -> usize {
+        self.length_of_field
+    }
+
+    /// Maximal number of stones of every player.
+    #[wasm_bindgen(getter)]
+    pub fn max_stones(&self) -> u32 {
+        self.indexer.max_stones
+    }
+
+    /// Number of positions in the tablebase.
+    pub fn size(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// True if the tablebase contains the position of `state`: the board has the length of the
+    /// tablebase, the game is not over and no player has too many stones.
+    pub fn contains(&self, state: &GameState) -> bool {
+        self.position_index(state).is_some()
+    }
+
+    /// Best move in `state` according to the tablebase: the fastest win, a move which keeps the
+    /// draw or the slowest loss. `undefined` if the tablebase does not contain the position.
+    pub fn best_move(&self, state: &GameState) -> Option<usize> {
+        self.position_index(state)?;
+        let mut best: Option<(usize, i32)> = None;
+        for (position, next_state) in state.legal_moves() {
+            // Higher values are better for the player to move in `state`
+            let value = match next_state.outcome() {
+                Some(outcome) if outcome.winner() == Some(state.curr_player) => i32::MAX,
+                // Drawn by the limit of plies without capture
+                Some(Outcome::Draw) => 0,
+                Some(_) => i32::MIN,
+                None => match self.probe(&next_state) {
+                    Some(TablebaseResult::Loss(distance)) => i32::MAX - 1 - distance as i32,
+                    Some(TablebaseResult::Draw) => 0,
+                    Some(TablebaseResult::Win(distance)) => -(i32::MAX - 1) + distance as i32,
+                    None => continue,
+                },
+            };
+            if best.is_none_or(|(_, best_value)| value > best_value) {
+                best = Some((position, value));
+            }
+        }
+        best.map(|(position, _)| position)
+    }
+
+    /// Distance of the result of `state` for the player to move in plies: positive for a win,
+    /// negative for a loss and 0 for a draw. `undefined` if the tablebase does not contain the
+    /// position.
+    pub fn distance_to_end(&self, state: &GameState) -> Option<i32> {
+        self.probe(state).map(|result| match result {
+            TablebaseResult::Win(distance) => distance as i32,
+            TablebaseResult::Loss(distance) => -(distance as i32),
+            TablebaseResult::Draw => 0,
+        })
+    }
+}
+
+impl Tablebase {
+    /// Solves all positions with 1 to `max_stones` stones per player on a board with
+    /// `length_of_field` cells per row. The time and memory grow quickly with the stones, e.g.
+    /// 3 stones on the standard board take a few seconds. Fails with `InvalidMaxStones` if
+    /// there would be more than `MAX_TABLEBASE_POSITIONS` positions.
+    pub fn generate(length_of_field: usize, max_stones: u32) -> Result<Tablebase, TablebaseError> {
+        let mut rules = RuleSet::new(length_of_field)
+            .map_err(|_| TablebaseError::InvalidLengthOfField(length_of_field))?;
+        if max_stones == 0 || max_stones > MAX_TABLEBASE_STONES {
+            return Err(TablebaseError::InvalidMaxStones(max_stones));
+        }
+        // Only the stones count, see the module documentation
+        rules.set_repetitions_for_draw(0);
+        rules.set_max_plies_without_capture(0);
+
+        let indexer = Indexer::new(rules.number_of_cells(), max_stones);
+        let size = indexer
+            .number_of_positions()
+            .ok_or(TablebaseError::InvalidMaxStones(max_stones))?;
+        let mut tablebase = Tablebase {
+            length_of_field,
+            indexer,
+            entries: vec![],
+        };
+        let mut results: Vec<Option<TablebaseResult>> = vec![None; size];
+        let mut queue = VecDeque::new();
+
+        // The moves of all positions which do not end the game, the successors of the position
+        // `index` are `successors[successor_starts[index]..successor_starts[index + 1]]`
+        let mut successor_starts: Vec<u32> = Vec::with_capacity(size + 1);
+        let mut successors: Vec<u32> = vec![];
+        for (index, result) in results.iter_mut().enumerate() {
+            successor_starts.push(successors.len() as u32);
+            let state = tablebase.state_at(&rules, index);
+            for (_, next_state) in state.legal_moves() {
+                if next_state.outcome().is_some() {
+                    *result = Some(TablebaseResult::Win(1));
+                } else {
+                    let next_index = tablebase
+                        .position_index(&next_state)
+                        .expect("Moves keep the positions in the tablebase.");
+                    successors.push(next_index as u32);
+                }
+            }
+            if result.is_some() {
+                queue.push_back(index);
+            }
+        }
+        successor_starts.push(successors.len() as u32);
+
+        // The same for the moves leading to every position
+        let mut predecessor_starts = vec![0u32; size + 1];
+        for next_index in successors.iter() {
+            predecessor_starts[*next_index as usize + 1] += 1;
+        }
+        for index in 0..size {
+            predecessor_starts[index + 1] += predecessor_starts[index];
+        }
+        let mut predecessors = vec![0u32; successors.len()];
+        let mut filled = predecessor_starts.clone();
+        for index in 0..size {
+            let moves = successor_starts[index] as usize..successor_starts[index + 1] as usize;
+            for next_index in successors[moves].iter() {
+                predecessors[filled[*next_index as usize] as usize] = index as u32;
+                filled[*next_index as usize] += 1;
+            }
+        }
+        drop(successors);
+
+        // Moves of every position which do not lead to a known win of the opponent yet
+        let mut undecided_moves: Vec<u8> = successor_starts
+            .windows(2)
+            .map(|starts| (starts[1] - starts[0]) as u8)
+            .collect();
+
+        // The distances of the positions in the queue never decrease
+        while let Some(index) = queue.pop_front() {
+            let result = results[index].expect("Positions in the queue are decided.");
+            let moves = predecessor_starts[index] as usize..predecessor_starts[index + 1] as usize;
+            for previous in predecessors[moves].iter() {
+                let previous = *previous as usize;
+                if results[previous].is_some() {
+                    continue;
+                }
+                match result {
+                    TablebaseResult::Loss(distance) => {
+                        results[previous] = Some(TablebaseResult::Win(distance + 1));
+                        queue.push_back(previous);
+                    }
+                    TablebaseResult::Win(distance) => {
+                        undecided_moves[previous] -= 1;
+                        if undecided_moves[previous] == 0 {
+                            results[previous] = Some(TablebaseResult::Loss(distance + 1));
+                            queue.push_back(previous);
+                        }
+                    }
+                    TablebaseResult::Draw => unreachable!("Draws are never queued."),
+                }
+            }
+        }
+
+        tablebase.entries = results
+            .into_iter()
+            .map(|result| result.unwrap_or(TablebaseResult::Draw).encode())
+            .collect();
+        Ok(tablebase)
+    }
+
+    /// Reads a tablebase written by `to_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Tablebase, TablebaseError> {
+        if bytes.len() < HEADER_LENGTH || !bytes.starts_with(TABLEBASE_MAGIC) {
+            return Err(TablebaseError::NotATablebase);
+        }
+        let header = &bytes[TABLEBASE_MAGIC.len()..HEADER_LENGTH];
+        if header[0] != FORMAT_VERSION {
+            return Err(TablebaseError::UnsupportedVersion(header[0]));
+        }
+        let length_of_field = header[1] as usize;
+        let rules = RuleSet::new(length_of_field)
+            .map_err(|_| TablebaseError::InvalidLengthOfField(length_of_field))?;
+        let max_stones = header[2] as u32;
+        if max_stones == 0 || max_stones > MAX_TABLEBASE_STONES {
+            return Err(TablebaseError::InvalidMaxStones(max_stones));
+        }
+
+        let indexer = Indexer::new(rules.number_of_cells(), max_stones);
+        let positions = indexer
+            .number_of_positions()
+            .ok_or(TablebaseError::InvalidMaxStones(max_stones))?;
+        let expected = HEADER_LENGTH + 2 * positions;
+        if bytes.len() != expected {
+            return Err(TablebaseError::WrongSize {
+                expected,
+                found: bytes.len(),
+            });
+        }
+        let entries = bytes[HEADER_LENGTH..]
+            .chunks_exact(2)
+            .map(|entry| u16::from_le_bytes([entry[0], entry[1]]))
+            .collect();
+        Ok(Tablebase {
+            length_of_field,
+            indexer,
+            entries,
+        })
+    }
+
+    /// Results of all positions of the tablebase.
+    pub fn results(&self) -> impl Iterator<Item = TablebaseResult> + '_ {
+        self.entries
+            .iter()
+            .map(|entry| TablebaseResult::decode(*entry))
+    }
+
+    /// Result of `state` for the player to move or `None` if the tablebase does not contain the
+    /// position.
+    pub fn probe(&self, state: &GameState) -> Option<TablebaseResult> {
+        self.position_index(state)
+            .map(|index| TablebaseResult::decode(self.entries[index]))
+    }
+
+    /// Index of the position of `state`: the player to move, then the stones of player 0 and
+    /// of player 1.
+    fn position_index(&self, state: &GameState) -> Option<usize> {
+        if state.rules.length_of_field != self.length_of_field || state.winner().is_some() {
+            return None;
+        }
+        let index0 = self.indexer.index(&state.fields_of_players[0].cells)? as usize;
+        let index1 = self.indexer.index(&state.fields_of_players[1].cells)? as usize;
+        let len = self.indexer.len();
+        Some((state.curr_player as usize * len + index0) * len + index1)
+    }
+
+    /// Position with the given index, see `position_index`.
+    fn state_at(&self, rules: &RuleSet, index: usize) -> GameState {
+        let len = self.indexer.len();
+        let curr_player = (index / (len * len)) as u8;
+        let cells0 = self.indexer.distributions[index / len % len].clone();
+        let cells1 = self.indexer.distributions[index % len].clone();
+        let mut state = GameState {
+            fields_of_players: [
+                OnePlayersField::new(Direction::Left, cells0),
+                OnePlayersField::new(Direction::Right, cells1),
+            ],
+            rules: *rules,
+            curr_player,
+            game_over: false,
+            move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
+        };
+        state.hash = zobrist::full_hash(&state);
+        state
+    }
+}
@@ -0,0 +1,43 @@
+//! `boabin engine`: serves the engine protocol (see `boa::engine`) on stdin/stdout.
+
+use boa::engine::Engine;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+
+/// Handles the commands from stdin until `quit` or the end of the input, which is handled like
+/// `quit`. Commands are read by a separate thread, so a running search can be stopped.
+pub fn serve() -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            match line.map(|line| sender.send(line)) {
+                Ok(Ok(())) => {}
+                _ => break,
+            }
+        }
+    });
+
+    let mut engine = Engine::new();
+    let mut output = io::stdout();
+    while !engine.has_quit() {
+        let answer = if engine.is_searching() {
+            match receiver.try_recv() {
+                Ok(line) => engine.execute(&line),
+                Err(TryRecvError::Empty) => engine.think(),
+                // Stops the search with `bestmove`, it would never end with `go infinite`
+                Err(TryRecvError::Disconnected) => engine.execute("quit"),
+            }
+        } else {
+            match receiver.recv() {
+                Ok(line) => engine.execute(&line),
+                Err(_) => break,
+            }
+        };
+        for line in answer {
+            writeln!(output, "{}", line)?;
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
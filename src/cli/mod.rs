@@ -0,0 +1,414 @@
+//! Command line interface of `boabin`.
+//!
+//! `boabin play` plays a game in the terminal (see `play`), `boabin engine` serves the engine
+//! protocol (see `boa::engine`), `boabin match` plays games between two AIs (see `tournament`),
+//! `boabin tablebase` generates an endgame tablebase (see `boa::ai::tablebase`), `boabin book`
+//! an opening book (see `boa::ai::book`) and `boabin evaluate` prints the evaluation of the
+//! initial position by the AI (the default without a command).
+
+mod engine;
+mod play;
+mod tournament;
+
+use boa::ai::book::{BookSettings, OpeningBook};
+use boa::ai::budget::SearchBudget;
+use boa::ai::difficulty::Difficulty;
+use boa::ai::evaluator::EvaluatorKind;
+use boa::ai::playout::PlayoutPolicyKind;
+use boa::ai::tablebase::{Tablebase, TablebaseResult};
+use boa::ai::{Algorithm, AI};
+use boa::engine::parse_variant;
+use boa::tournament::Sprt;
+use boa::GameState;
+use std::fs;
+use std::io;
+use std::rc::Rc;
+
+use self::play::{PlayOptions, Player};
+use self::tournament::MatchOptions;
+
+const USAGE: &str = "Usage: boabin [COMMAND] [OPTIONS]
+
+Commands:
+  evaluate   Print the evaluations of the initial position by the AI (default)
+  play       Play a game in the terminal
+  engine     Serve the engine protocol on stdin/stdout
+  match      Play games between two AIs and estimate their Elo difference
+  tablebase  Generate an endgame tablebase
+  book       Generate an opening book
+  help       Print this help
+
+Options of `play`:
+  --player1 <human|ai>        Who plays for player 1, who moves first (default: human)
+  --player2 <human|ai>        Who plays for player 2 (default: ai)
+  --difficulty <LEVEL>        beginner, easy, medium, hard or expert (default: medium)
+  --algorithm <ALGORITHM>     flat, mcts or alphabeta (default: given by the difficulty)
+  --playouts <N>              Playouts per possible move of the AI
+  --time <MILLIS>             Maximal time of the AI per move in milliseconds
+  --depth <PLIES>             Maximal depth of the alpha-beta search
+  --seed <N>                  Seed of the random numbers of the AI
+  --tablebase <FILE>          Play the positions of the tablebase perfectly
+  --book <FILE>               Play the moves of the opening book
+  --position <NOTATION>       Start from a position in position notation, e.g.
+                              \"2222222222222222/2222222222222222 0 L8\"
+
+Options of `match`:
+  --engine1 <SPEC>            Settings of the first AI (default: difficulty=medium)
+  --engine2 <SPEC>            Settings of the second AI (default: difficulty=medium)
+  --games <N>                 Maximal number of games, the colours alternate (default: 100)
+  --sprt <ELO0,ELO1>          Stop as soon as the SPRT of the Elo difference ELO0 against ELO1
+                              decides (alpha = beta = 0.05)
+  --record <FILE>             Write the records of all games to FILE
+  --position <NOTATION>       Start every game from this position
+
+A SPEC is a comma separated list of settings, e.g.
+\"name=greedy,algorithm=mcts,playouts=500,policy=greedycapture,seed=1\":
+  name, difficulty, algorithm, policy (uniformrandom, greedycapture or softmax),
+  evaluator (stonedifference, rowweighted, mobility, vulnerability or balanced),
+  playouts, time, depth, seed, tablebase and book have the meaning of the options of `play`.
+
+Options of `tablebase`:
+  --length <CELLS>            Cells per row of the board (default: 8)
+  --stones <N>                Maximal number of stones per player (default: 3)
+  --output <FILE>             File of the tablebase (default: tablebase.bin)
+
+Options of `book`:
+  --engine <SPEC>             AI which searches every position with its budget (see `match`)
+  --plies <N>                 Plies from the initial position which are searched (default: 6)
+  --moves <N>                 Maximal number of moves per position (default: 3)
+  --margin <VALUE>            Store the moves whose value (between -1 and 1) is at most this
+                              much worse than the best one (default: 0.1)
+  --position <NOTATION>       Initial position of the book (default: the standard position)
+  --output <FILE>             File of the book (default: book.bin)";
+
+/// Runs the command given by the command line arguments (without the name of the program).
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args.first().map(String::as_str) {
+        None | Some("evaluate") => {
+            evaluate_initial_position();
+            Ok(())
+        }
+        Some("play") => {
+            let options = parse_play_options(&args[1..])?;
+            let stdin = io::stdin();
+            play::play(&options, &mut stdin.lock(), &mut io::stdout()).map_err(|e| e.to_string())
+        }
+        Some("engine") => engine::serve().map_err(|e| e.to_string()),
+        Some("match") => {
+            let options = parse_match_options(&args[1..])?;
+            tournament::run_match(&options, &mut io::stdout()).map_err(|e| e.to_string())
+        }
+        Some("tablebase") => generate_tablebase(&args[1..]),
+        Some("book") => generate_book(&args[1..]),
+        Some("help") | Some("--help") | Some("-h") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(command) => Err(format!("Unknown command '{}'.\n\n{}", command, USAGE)),
+    }
+}
+
+/// Settings of an AI given on the command line.
+pub struct AIOptions {
+    pub difficulty: Difficulty,
+    /// Algorithm instead of the one of the difficulty.
+    pub algorithm: Option<Algorithm>,
+    pub playout_policy: Option<PlayoutPolicyKind>,
+    pub evaluator: Option<EvaluatorKind>,
+    /// Limits of the AI per move instead of the budget of the difficulty.
+    pub max_playouts: Option<u32>,
+    pub max_time_in_millis: Option<u32>,
+    pub max_depth: Option<u32>,
+    pub seed: Option<u64>,
+    pub tablebase: Option<Rc<Tablebase>>,
+    pub opening_book: Option<Rc<OpeningBook>>,
+}
+
+impl Default for AIOptions {
+    fn default() -> Self {
+        AIOptions {
+            difficulty: Difficulty::Medium,
+            algorithm: None,
+            playout_policy: None,
+            evaluator: None,
+            max_playouts: None,
+            max_time_in_millis: None,
+            max_depth: None,
+            seed: None,
+            tablebase: None,
+            opening_book: None,
+        }
+    }
+}
+
+impl AIOptions {
+    /// Creates the AI, `seed_offset` is added to the seed.
+    pub fn create_ai(&self, seed_offset: u64) -> AI {
+        let mut ai = AI::new();
+        ai.set_difficulty(self.difficulty);
+        if let Some(algorithm) = self.algorithm {
+            ai.set_algorithm(algorithm);
+        }
+        if let Some(playout_policy) = self.playout_policy {
+            ai.set_playout_policy_kind(playout_policy);
+        }
+        if let Some(evaluator) = self.evaluator {
+            ai.set_evaluator_kind(evaluator);
+        }
+        if let Some(max_depth) = self.max_depth {
+            ai.set_max_depth(max_depth);
+        }
+        if self.max_playouts.is_some() || self.max_time_in_millis.is_some() {
+            ai.set_budget(&SearchBudget::new(
+                self.max_playouts,
+                self.max_time_in_millis.map(u128::from),
+            ));
+        }
+        if let Some(seed) = self.seed {
+            ai.set_seed(seed.wrapping_add(seed_offset));
+        }
+        ai.set_tablebase(self.tablebase.clone());
+        ai.set_opening_book(self.opening_book.clone());
+        ai
+    }
+}
+
+fn generate_tablebase(args: &[String]) -> Result<(), String> {
+    let mut length_of_field = 8;
+    let mut max_stones = 3;
+    let mut output = "tablebase.bin".to_string();
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value of option '{}'.\n\n{}", option, USAGE))?;
+        match option.as_str() {
+            "--length" => length_of_field = parse_number(option, value)?,
+            "--stones" => max_stones = parse_number(option, value)?,
+            "--output" => output = value.clone(),
+            _ => return Err(format!("Unknown option '{}'.\n\n{}", option, USAGE)),
+        }
+    }
+
+    let tablebase = Tablebase::generate(length_of_field, max_stones)
+        .map_err(|e| format!("Cannot generate the tablebase: {}", e))?;
+    let (mut wins, mut losses, mut draws, mut longest_win) = (0, 0, 0, 0);
+    for result in tablebase.results() {
+        match result {
+            TablebaseResult::Win(distance) => {
+                wins += 1;
+                longest_win = longest_win.max(distance);
+            }
+            TablebaseResult::Loss(_) => losses += 1,
+            TablebaseResult::Draw => draws += 1,
+        }
+    }
+    fs::write(&output, tablebase.to_bytes())
+        .map_err(|e| format!("Cannot write '{}': {}", output, e))?;
+    println!(
+        "{} positions: {} wins, {} losses, {} draws, longest win in {} plies. Written to {}.",
+        tablebase.size(),
+        wins,
+        losses,
+        draws,
+        longest_win,
+        output
+    );
+    Ok(())
+}
+
+fn load_tablebase(path: &str) -> Result<Rc<Tablebase>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Cannot read '{}': {}", path, e))?;
+    Tablebase::from_bytes(&bytes)
+        .map(Rc::new)
+        .map_err(|e| format!("Invalid tablebase '{}': {}", path, e))
+}
+
+fn generate_book(args: &[String]) -> Result<(), String> {
+    let mut ai_options = AIOptions::default();
+    let mut settings = BookSettings::default();
+    let mut initial_state = GameState::default();
+    let mut output = "book.bin".to_string();
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value of option '{}'.\n\n{}", option, USAGE))?;
+        match option.as_str() {
+            "--engine" => ai_options = parse_ai_spec(value, "")?.1,
+            "--plies" => settings.max_plies = parse_number(option, value)?,
+            "--moves" => settings.max_moves = parse_number(option, value)?,
+            "--margin" => settings.margin = parse_number(option, value)?,
+            "--position" => {
+                initial_state = GameState::from_notation(value)
+                    .map_err(|e| format!("Invalid position '{}': {}", value, e))?
+            }
+            "--output" => output = value.clone(),
+            _ => return Err(format!("Unknown option '{}'.\n\n{}", option, USAGE)),
+        }
+    }
+
+    let ai = ai_options.create_ai(0);
+    settings.budget = ai.budget();
+    let book = OpeningBook::generate(&ai, &initial_state, &settings)
+        .map_err(|e| format!("Cannot generate the opening book: {}", e))?;
+    fs::write(&output, book.to_bytes()).map_err(|e| format!("Cannot write '{}': {}", output, e))?;
+    println!(
+        "{} moves in {} positions. Written to {}.",
+        book.size(),
+        book.number_of_positions(),
+        output
+    );
+    Ok(())
+}
+
+fn load_opening_book(path: &str) -> Result<Rc<OpeningBook>, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Cannot read '{}': {}", path, e))?;
+    OpeningBook::from_bytes(&bytes)
+        .map(Rc::new)
+        .map_err(|e| format!("Invalid opening book '{}': {}", path, e))
+}
+
+fn evaluate_initial_position() {
+    for _ in 0..5 {
+        let game = GameState::default(); // beginning of the game
+        let ai = AI::new();
+
+        let evaluation = ai.evaluate(&game, &SearchBudget::time(1000));
+
+        match evaluation {
+            Ok(evaluation) => {
+                println!("{:?}", evaluation.win_differences());
+            }
+            Err(_) => panic!("ohoh..."),
+        }
+    }
+}
+
+fn parse_play_options(args: &[String]) -> Result<PlayOptions, String> {
+    let mut options = PlayOptions::default();
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value of option '{}'.\n\n{}", option, USAGE))?;
+        match option.as_str() {
+            "--player1" => options.players[0] = parse_player(value)?,
+            "--player2" => options.players[1] = parse_player(value)?,
+            "--difficulty" => options.ai.difficulty = parse_difficulty(value)?,
+            "--algorithm" => options.ai.algorithm = Some(parse_algorithm(value)?),
+            "--playouts" => options.ai.max_playouts = Some(parse_number(option, value)?),
+            "--time" => options.ai.max_time_in_millis = Some(parse_number(option, value)?),
+            "--depth" => options.ai.max_depth = Some(parse_number(option, value)?),
+            "--seed" => options.ai.seed = Some(parse_number(option, value)?),
+            "--tablebase" => options.ai.tablebase = Some(load_tablebase(value)?),
+            "--book" => options.ai.opening_book = Some(load_opening_book(value)?),
+            "--position" => {
+                options.initial_state = GameState::from_notation(value)
+                    .map_err(|e| format!("Invalid position '{}': {}", value, e))?
+            }
+            _ => return Err(format!("Unknown option '{}'.\n\n{}", option, USAGE)),
+        }
+    }
+    Ok(options)
+}
+
+fn parse_match_options(args: &[String]) -> Result<MatchOptions, String> {
+    let mut options = MatchOptions::default();
+    let mut args = args.iter();
+    while let Some(option) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value of option '{}'.\n\n{}", option, USAGE))?;
+        match option.as_str() {
+            "--engine1" => options.engines[0] = parse_ai_spec(value, &options.engines[0].0)?,
+            "--engine2" => options.engines[1] = parse_ai_spec(value, &options.engines[1].0)?,
+            "--games" => options.games = parse_number(option, value)?,
+            "--sprt" => options.sprt = Some(parse_sprt(value)?),
+            "--record" => options.record_file = Some(value.clone()),
+            "--position" => {
+                options.initial_state = GameState::from_notation(value)
+                    .map_err(|e| format!("Invalid position '{}': {}", value, e))?
+            }
+            _ => return Err(format!("Unknown option '{}'.\n\n{}", option, USAGE)),
+        }
+    }
+    Ok(options)
+}
+
+/// Name and settings of an AI, e.g. `name=fast,algorithm=flat,playouts=100`. `default_name`
+/// is used without `name`.
+fn parse_ai_spec(spec: &str, default_name: &str) -> Result<(String, AIOptions), String> {
+    let mut name = default_name.to_string();
+    let mut options = AIOptions::default();
+    for setting in spec.split(',').filter(|setting| !setting.is_empty()) {
+        let (key, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid setting '{}', expected key=value.", setting))?;
+        match key {
+            "name" => name = value.to_string(),
+            "difficulty" => options.difficulty = parse_difficulty(value)?,
+            "algorithm" => options.algorithm = Some(parse_algorithm(value)?),
+            "policy" => {
+                options.playout_policy = Some(parse_variant(
+                    "playout policy",
+                    value,
+                    &PlayoutPolicyKind::ALL,
+                )?)
+            }
+            "evaluator" => {
+                options.evaluator = Some(parse_variant("evaluator", value, &EvaluatorKind::ALL)?)
+            }
+            "playouts" => options.max_playouts = Some(parse_number(key, value)?),
+            "time" => options.max_time_in_millis = Some(parse_number(key, value)?),
+            "depth" => options.max_depth = Some(parse_number(key, value)?),
+            "seed" => options.seed = Some(parse_number(key, value)?),
+            "tablebase" => options.tablebase = Some(load_tablebase(value)?),
+            "book" => options.opening_book = Some(load_opening_book(value)?),
+            _ => return Err(format!("Unknown setting '{}' of an AI.", key)),
+        }
+    }
+    Ok((name, options))
+}
+
+/// The Elo differences of both hypotheses, e.g. `0,10`.
+fn parse_sprt(value: &str) -> Result<Sprt, String> {
+    let invalid = || format!("Invalid SPRT bounds '{}', expected ELO0,ELO1.", value);
+    let (elo0, elo1) = value.split_once(',').ok_or_else(invalid)?;
+    let elo0: f64 = elo0.trim().parse().map_err(|_| invalid())?;
+    let elo1: f64 = elo1.trim().parse().map_err(|_| invalid())?;
+    if elo0 >= elo1 {
+        return Err(format!("ELO0 must be less than ELO1 in '{}'.", value));
+    }
+    Ok(Sprt::new(elo0, elo1))
+}
+
+fn parse_player(value: &str) -> Result<Player, String> {
+    match value.to_lowercase().as_str() {
+        "human" => Ok(Player::Human),
+        "ai" | "computer" => Ok(Player::Computer),
+        _ => Err(format!("Unknown player '{}', expected human or ai.", value)),
+    }
+}
+
+fn parse_difficulty(value: &str) -> Result<Difficulty, String> {
+    parse_variant("difficulty", value, &Difficulty::ALL)
+}
+
+fn parse_algorithm(value: &str) -> Result<Algorithm, String> {
+    match value.to_lowercase().as_str() {
+        "flat" => Ok(Algorithm::FlatMonteCarlo),
+        "mcts" => Ok(Algorithm::MonteCarloTreeSearch),
+        "alphabeta" => Ok(Algorithm::AlphaBeta),
+        _ => Err(format!(
+            "Unknown algorithm '{}', expected flat, mcts or alphabeta.",
+            value
+        )),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' of option '{}'.", value, option))
+}
@@ -0,0 +1,207 @@
+//! A game in the terminal between humans and/or the AI.
+//!
+//! The board is printed with the `Display` of `GameState`. Humans enter their moves as row and
+//! column relative to their side of the board (see `GameState::make_move_via_row_col_wasm`):
+//! row 0 is the back row, row 1 the front row and the columns are counted from the left,
+//! starting at 0.
+
+use boa::ai::AI;
+use boa::session::GameSession;
+use boa::{GameError, GameState, Outcome};
+use std::io::{self, BufRead, Write};
+
+use super::AIOptions;
+
+const HELP: &str =
+    "Enter the row and the column of your move, e.g. `1 3`. Rows and columns are seen
+from your side of the board: row 0 is your back row, row 1 your front row, the columns are
+counted from the left starting at 0.
+
+Other commands:
+  moves      List your legal moves
+  undo       Take back your last move (and the answer of the AI)
+  notation   Print the position in position notation
+  help       Print this help
+  quit       End the game";
+
+/// Who makes the moves of a player.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Player {
+    Human,
+    Computer,
+}
+
+/// Settings of `boabin play`, the AI settings apply to every player of the AI.
+pub struct PlayOptions {
+    pub players: [Player; 2],
+    pub ai: AIOptions,
+    pub initial_state: GameState,
+}
+
+impl Default for PlayOptions {
+    fn default() -> Self {
+        PlayOptions {
+            players: [Player::Human, Player::Computer],
+            ai: AIOptions::default(),
+            initial_state: GameState::default(),
+        }
+    }
+}
+
+/// What a human did in his/her turn.
+enum Turn {
+    /// A move was made or taken back.
+    Played,
+    Quit,
+}
+
+/// Plays a game until it is over or a human quits. Moves of humans are read from `input`.
+pub fn play(
+    options: &PlayOptions,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let ais = [0, 1].map(|player| match options.players[player] {
+        // Different random numbers for both players if the AI plays against itself
+        Player::Computer => Some(options.ai.create_ai(player as u64)),
+        Player::Human => None,
+    });
+    let mut session = GameSession::new(&options.initial_state);
+
+    loop {
+        let state = session.current_state();
+        writeln!(output, "\n{}\n", state)?;
+        if let Some(outcome) = session.outcome() {
+            writeln!(output, "{}", describe_outcome(outcome))?;
+            return Ok(());
+        }
+
+        let player = state.curr_player as usize;
+        match &ais[player] {
+            Some(ai) => {
+                let position = ai
+                    .next_move(&state)
+                    .and_then(|position| session.make_move(position).map(|_| position))
+                    .map_err(|e| io::Error::other(format!("The AI could not move: {}", e)))?;
+                let (row, col) = state.get_row_col_from_position(player, position);
+                writeln!(
+                    output,
+                    "Player {} (AI) plays row {}, column {}.",
+                    player + 1,
+                    row,
+                    col
+                )?;
+            }
+            None => {
+                if let Turn::Quit = human_turn(&mut session, &ais, input, output)? {
+                    writeln!(output, "Game ended.")?;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Reads commands of the current player until a move is made or taken back.
+fn human_turn(
+    session: &mut GameSession,
+    ais: &[Option<AI>; 2],
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<Turn> {
+    let state = session.current_state();
+    let player = state.curr_player as usize;
+    loop {
+        write!(
+            output,
+            "Player {}, your move (row column) or `help`: ",
+            player + 1
+        )?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(Turn::Quit);
+        }
+
+        match line.trim() {
+            "" => {}
+            "quit" | "q" => return Ok(Turn::Quit),
+            "help" | "h" | "?" => writeln!(output, "{}", HELP)?,
+            "notation" => writeln!(output, "{}", state.to_notation())?,
+            "moves" => {
+                let moves: Vec<String> = state
+                    .legal_moves()
+                    .map(|(position, _)| {
+                        let (row, col) = state.get_row_col_from_position(player, position);
+                        format!("{} {}", row, col)
+                    })
+                    .collect();
+                writeln!(output, "Legal moves: {}", moves.join(", "))?;
+            }
+            "undo" => {
+                if undo_turn(session, ais) {
+                    return Ok(Turn::Played);
+                }
+                writeln!(output, "There is no move to take back.")?;
+            }
+            command => match parse_row_col(command) {
+                Some((row, col)) => match session.make_move_via_row_col(row, col) {
+                    Ok(_) => return Ok(Turn::Played),
+                    Err(e) => writeln!(output, "Illegal move: {} ({}).", describe_error(e), e)?,
+                },
+                None => writeln!(
+                    output,
+                    "Unknown command `{}`, enter `help` for the commands.",
+                    command
+                )?,
+            },
+        }
+    }
+}
+
+/// Takes back moves until a human is to move again. Returns false if there was nothing to take
+/// back.
+fn undo_turn(session: &mut GameSession, ais: &[Option<AI>; 2]) -> bool {
+    let ply = session.current_ply();
+    while session.undo() {
+        let player = session.current_state_ref().curr_player as usize;
+        if ais[player].is_none() {
+            return true;
+        }
+    }
+    // Only moves of the AI since the beginning of the game
+    session.jump_to_ply(ply);
+    false
+}
+
+/// Row and column separated by whitespace or a comma, e.g. `1 3` or `1,3`.
+fn parse_row_col(command: &str) -> Option<(usize, usize)> {
+    let mut parts = command
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|part| !part.is_empty());
+    let row = parts.next()?.parse().ok()?;
+    let col = parts.next()?.parse().ok()?;
+    match parts.next() {
+        Some(_) => None,
+        None => Some((row, col)),
+    }
+}
+
+fn describe_error(error: GameError) -> &'static str {
+    match error {
+        GameError::PositionOutsideField => "the position is outside of the field",
+        GameError::CellEmpty => "the cell is empty",
+        GameError::NoMoveFound => "the game is over",
+        GameError::RepeatingPosition => "the move would repeat a position",
+        GameError::InvalidRuleSet => "the rules are invalid",
+        GameError::InvalidSetup => "the setup is invalid",
+    }
+}
+
+fn describe_outcome(outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Player0Wins => "Player 1 wins!",
+        Outcome::Player1Wins => "Player 2 wins!",
+        Outcome::Draw => "The game is drawn.",
+    }
+}
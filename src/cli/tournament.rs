@@ -0,0 +1,129 @@
+//! `boabin match`: games between two AIs with alternating colours (see `boa::tournament`).
+//!
+//! The result of every game is printed with the score so far, the match ends after the given
+//! number of games or as soon as the SPRT decides. The summary contains the Elo difference of
+//! the first AI with its 95% confidence interval.
+
+use boa::ai::AI;
+use boa::record::TAG_RESULT;
+use boa::tournament::{first_is_player0, play_match_game, MatchScore, Sprt, SprtStatus};
+use boa::GameState;
+use std::fs::File;
+use std::io::{self, Write};
+
+use super::AIOptions;
+
+/// Settings of `boabin match`.
+pub struct MatchOptions {
+    /// Names and settings of both AIs.
+    pub engines: [(String, AIOptions); 2],
+    pub games: u32,
+    pub sprt: Option<Sprt>,
+    /// File for the records of all games.
+    pub record_file: Option<String>,
+    pub initial_state: GameState,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        MatchOptions {
+            engines: [
+                ("engine1".to_string(), AIOptions::default()),
+                ("engine2".to_string(), AIOptions::default()),
+            ],
+            games: 100,
+            sprt: None,
+            record_file: None,
+            initial_state: GameState::default(),
+        }
+    }
+}
+
+/// Plays the match and prints the results to `output`.
+pub fn run_match(options: &MatchOptions, output: &mut impl Write) -> io::Result<()> {
+    let ais: [AI; 2] = [0, 1].map(|engine| options.engines[engine].1.create_ai(engine as u64));
+    let names = [options.engines[0].0.as_str(), options.engines[1].0.as_str()];
+    let mut record_file = match &options.record_file {
+        Some(path) => Some(File::create(path)?),
+        None => None,
+    };
+
+    let mut score = MatchScore::default();
+    let mut sprt_status = SprtStatus::Continue;
+    for game in 0..options.games {
+        let record = play_match_game(&ais, names, game, &options.initial_state)
+            .map_err(|e| io::Error::other(format!("Game {} failed: {}", game + 1, e)))?;
+        let outcome = record.outcome().expect("Games are played to the end.");
+        score.add(outcome, first_is_player0(game));
+        if let Some(file) = &mut record_file {
+            writeln!(file, "{}", record)?;
+        }
+
+        let (player0, player1) = if first_is_player0(game) {
+            (names[0], names[1])
+        } else {
+            (names[1], names[0])
+        };
+        writeln!(
+            output,
+            "Game {} ({} vs {}): {} in {} plies, score {}",
+            game + 1,
+            player0,
+            player1,
+            record.tag(TAG_RESULT).unwrap_or("*"),
+            record.moves().len(),
+            format_score(&score)
+        )?;
+        output.flush()?;
+
+        if let Some(sprt) = &options.sprt {
+            sprt_status = sprt.status(&score);
+            if sprt_status != SprtStatus::Continue {
+                break;
+            }
+        }
+    }
+
+    writeln!(output)?;
+    writeln!(
+        output,
+        "Score of {} vs {}: {}",
+        names[0],
+        names[1],
+        format_score(&score)
+    )?;
+    if let (Some(elo), Some(error)) = (score.elo_difference(), score.elo_error()) {
+        writeln!(output, "Elo difference: {:.1} +/- {:.1}", elo, error)?;
+    }
+    if let Some(sprt) = &options.sprt {
+        let (lower, upper) = sprt.bounds();
+        let decision = match sprt_status {
+            SprtStatus::Continue => "no decision",
+            SprtStatus::AcceptH0 => "H0 accepted",
+            SprtStatus::AcceptH1 => "H1 accepted",
+        };
+        writeln!(
+            output,
+            "SPRT (elo0 {}, elo1 {}): LLR {:.2} ({:.2}, {:.2}), {}",
+            sprt.elo0,
+            sprt.elo1,
+            sprt.log_likelihood_ratio(&score),
+            lower,
+            upper,
+            decision
+        )?;
+    }
+    Ok(())
+}
+
+/// Wins, losses and draws of the first AI with the points per game.
+fn format_score(score: &MatchScore) -> String {
+    format!(
+        "{} - {} - {} [{:.3}] {} games",
+        score.wins,
+        score.losses,
+        score.draws,
+        score.score().unwrap_or(0.5),
+        score.games()
+    )
+}
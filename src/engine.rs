@@ -0,0 +1,499 @@
+//! Line based engine protocol (in the spirit of UCI in chess), e.g. to use the AI from external
+//! GUIs, tournament managers and scripts. `boabin engine` serves it on stdin/stdout.
+//!
+//! Every command is a single line, the engine answers with zero or more lines:
+//!
+//! * `boa`: identifies the engine with `id name ...`, `id author ...` and `option ...` lines,
+//!   followed by `boaok`.
+//! * `isready`: answered with `readyok`.
+//! * `setoption name <name> value <value>`: changes a setting of the AI, see `OPTIONS`.
+//! * `rules <rules>`: starts a new game with the rules in notation, e.g. `rules L6R3C100` (see
+//!   `crate::notation`).
+//! * `newgame`: starts a new game from the standard position with the current rules.
+//! * `position startpos [moves <move> ...]` or `position notation <position> [moves <move>
+//!   ...]`: sets the position, optionally followed by moves in move notation, e.g. `c2 f2`.
+//! * `moves <move> ...`: makes moves in the current position.
+//! * `d`: prints the position (`position <notation>`), the legal moves (`legalmoves ...`) and
+//!   the result (`result ...`, see `crate::record`).
+//! * `go [playouts <n>] [movetime <millis>] [infinite]`: searches the current position. The
+//!   playouts are counted per possible move, without any limit the budget of the AI is used.
+//!   While searching, `info time <millis> iterations <n> bestmove <move> winrate <rate> score
+//!   <score>` lines are sent. The search ends with `bestmove <move>` (`bestmove none` if the
+//!   game is over), the best move of the search unless the option `Difficulty` is set, which
+//!   chooses the move with the noise of the difficulty level.
+//! * `stop`: ends the search.
+//! * `quit`: ends the search and the engine.
+//!
+//! Other commands received while searching (except `isready`) are handled in order after the
+//! search ended, so a script may send `go` and the following commands at once. `stop` and
+//! `quit` are handled at once: after `stop`, the commands received while searching are handled,
+//! `quit` drops them.
+//!
+//! Invalid commands are answered with `error <message>`.
+//!
+//! The engine does not read its input itself: `execute` handles a command and `think` continues
+//! the running search a little, so the caller can check for new commands in between.
+
+use crate::ai::difficulty::{move_values, Difficulty};
+use crate::ai::evaluator::EvaluatorKind;
+use crate::ai::playout::PlayoutPolicyKind;
+use crate::ai::search::Search;
+use crate::ai::{Algorithm, AI};
+use crate::notation::parse_rules;
+use crate::record::result_of;
+use crate::session::GameSession;
+use crate::{GameState, RuleSet};
+use instant::Instant;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+pub const ENGINE_NAME: &str = "boa";
+pub const ENGINE_AUTHOR: &str = "seryj";
+
+/// Options of `setoption` with their possible values.
+pub const OPTIONS: [(&str, &str); 8] = [
+    (
+        "Difficulty",
+        "type combo var Beginner var Easy var Medium var Hard var Expert",
+    ),
+    (
+        "Algorithm",
+        "type combo var FlatMonteCarlo var MonteCarloTreeSearch var AlphaBeta",
+    ),
+    ("ExplorationConstant", "type string"),
+    ("MaxDepth", "type spin min 1 max 64"),
+    (
+        "Evaluator",
+        "type combo var StoneDifference var RowWeighted var Mobility var Vulnerability var Balanced",
+    ),
+    (
+        "PlayoutPolicy",
+        "type combo var UniformRandom var GreedyCapture var Softmax",
+    ),
+    ("ReuseSearch", "type check default true"),
+    ("Seed", "type string"),
+];
+
+/// The Monte Carlo searches send an `info` line after this time at the latest.
+const INFO_INTERVAL_IN_MILLIS: u32 = 100;
+
+/// Playouts per possible move between two `info` lines if the number of playouts is limited.
+const PLAYOUTS_PER_INFO: u32 = 20;
+
+/// Limits of a running search given by `go`.
+struct Go {
+    max_playouts: Option<u32>,
+    max_time_in_millis: Option<u32>,
+    infinite: bool,
+    starttime: Instant,
+    playouts_done: u32,
+}
+
+/// State of the engine: the AI, the game and the running search.
+pub struct Engine {
+    ai: AI,
+    rules: RuleSet,
+    session: GameSession,
+    /// The last search, continued if the next position can be reached from it.
+    search: Option<Search>,
+    go: Option<Go>,
+    /// Commands received while searching.
+    pending: VecDeque<String>,
+    quit: bool,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        let mut ai = AI::new();
+        ai.set_difficulty(Difficulty::Medium);
+        // Always the best move, only the `Difficulty` option adds noise to the choice
+        ai.set_move_noise(0.0, 0.0);
+        Engine {
+            ai,
+            rules: RuleSet::default(),
+            session: GameSession::default(),
+            search: None,
+            go: None,
+            pending: VecDeque::new(),
+            quit: false,
+        }
+    }
+
+    /// True after `quit`.
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// True while a search started by `go` is running, see `think`.
+    pub fn is_searching(&self) -> bool {
+        self.go.is_some()
+    }
+
+    /// The current position.
+    pub fn state(&self) -> &GameState {
+        self.session.current_state_ref()
+    }
+
+    /// Handles a command and returns the lines of the answer.
+    pub fn execute(&mut self, line: &str) -> Vec<String> {
+        let mut tokens = line.split_whitespace();
+        let command = match tokens.next() {
+            Some(command) => command,
+            None => return vec![],
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        let mut answer = vec![];
+        if self.is_searching() {
+            match command {
+                "isready" => {}
+                "stop" | "quit" => answer.extend(self.finish_search()),
+                _ => {
+                    self.pending.push_back(line.to_string());
+                    return answer;
+                }
+            }
+        }
+        let result = match command {
+            "boa" => Ok(identify()),
+            "isready" => Ok(vec!["readyok".to_string()]),
+            "setoption" => self.set_option(&args),
+            "rules" => self.set_rules(&args),
+            "newgame" => {
+                self.session = GameSession::new(&GameState::new(&self.rules));
+                self.search = None;
+                Ok(vec![])
+            }
+            "position" => self.set_position(&args),
+            "moves" => self.make_moves(&args),
+            "d" => Ok(self.describe_position()),
+            "go" => self.go(&args),
+            "stop" => Ok(vec![]),
+            "quit" => {
+                self.quit = true;
+                self.pending.clear();
+                Ok(vec![])
+            }
+            _ => Err(format!("unknown command '{}'", command)),
+        };
+        match result {
+            Ok(lines) => answer.extend(lines),
+            Err(message) => answer.push(format!("error {}", message)),
+        }
+        if command == "stop" {
+            answer.extend(self.execute_pending());
+        }
+        answer
+    }
+
+    /// Handles the commands received while searching until one of them starts a new search.
+    fn execute_pending(&mut self) -> Vec<String> {
+        let mut answer = vec![];
+        while !self.is_searching() && !self.quit {
+            match self.pending.pop_front() {
+                Some(line) => answer.extend(self.execute(&line)),
+                None => break,
+            }
+        }
+        answer
+    }
+
+    /// Continues the running search a little (at most about `INFO_INTERVAL_IN_MILLIS` for the
+    /// Monte Carlo searches) and returns an `info` line, followed by `bestmove` if the search
+    /// is over. Does nothing if no search is running.
+    pub fn think(&mut self) -> Vec<String> {
+        let (go, search) = match (&mut self.go, &mut self.search) {
+            (Some(go), Some(search)) => (go, search),
+            _ => return vec![],
+        };
+
+        let elapsed = go.starttime.elapsed().as_millis() as u32;
+        let remaining_time = go
+            .max_time_in_millis
+            .map(|max_time| max_time.saturating_sub(elapsed).max(1));
+        if search.algorithm() == Algorithm::AlphaBeta {
+            // Aborted iterations are lost, so the time is not split up
+            match remaining_time {
+                Some(remaining_time) => search.step_for(remaining_time),
+                None => search.step(1),
+            }
+        } else {
+            match go.max_playouts {
+                Some(max_playouts) => {
+                    let playouts = PLAYOUTS_PER_INFO.min(max_playouts - go.playouts_done);
+                    search.step(playouts);
+                    go.playouts_done += playouts;
+                }
+                None => search.step_for(
+                    remaining_time
+                        .unwrap_or(INFO_INTERVAL_IN_MILLIS)
+                        .min(INFO_INTERVAL_IN_MILLIS),
+                ),
+            }
+        }
+
+        let elapsed = go.starttime.elapsed().as_millis() as u32;
+        let is_over = !go.infinite
+            && (go
+                .max_playouts
+                .is_some_and(|max_playouts| go.playouts_done >= max_playouts)
+                || go
+                    .max_time_in_millis
+                    .is_some_and(|max_time| elapsed >= max_time));
+        let is_over = is_over || search.is_finished();
+        let mut lines = vec![self.info()];
+        if is_over {
+            lines.extend(self.finish_search());
+            lines.extend(self.execute_pending());
+        }
+        lines
+    }
+
+    /// Ends the running search with `bestmove`.
+    fn finish_search(&mut self) -> Vec<String> {
+        if self.go.take().is_none() {
+            return vec![];
+        }
+        let state = self.state();
+        let best_move = self
+            .search
+            .as_ref()
+            .and_then(|search| self.ai.select_move(&search.evaluation()))
+            // Stopped before anything was searched
+            .or_else(|| state.legal_moves().next().map(|(position, _)| position))
+            .and_then(|position| state.move_to_notation(position).ok());
+        vec![format!(
+            "bestmove {}",
+            best_move.unwrap_or_else(|| "none".to_string())
+        )]
+    }
+
+    /// `info` line of the running search.
+    fn info(&self) -> String {
+        let go = self.go.as_ref().expect("Only called while searching.");
+        let evaluation = match &self.search {
+            Some(search) => search.evaluation(),
+            None => return "info".to_string(),
+        };
+        let mut info = format!(
+            "info time {} iterations {}",
+            go.starttime.elapsed().as_millis(),
+            evaluation
+                .visits()
+                .iter()
+                .map(|visits| i64::from(*visits))
+                .sum::<i64>()
+        );
+        if let Some(position) = evaluation.recommended_move() {
+            if let Ok(notation) = self.state().move_to_notation(position) {
+                info.push_str(&format!(" bestmove {}", notation));
+            }
+            if let Some((_, value)) = move_values(&evaluation)
+                .into_iter()
+                .find(|(other, _)| *other == position)
+            {
+                info.push_str(&format!(" winrate {:.3}", (value + 1.0) / 2.0));
+            }
+            info.push_str(&format!(" score {}", evaluation.scores()[position]));
+        }
+        info
+    }
+
+    fn go(&mut self, args: &[&str]) -> Result<Vec<String>, String> {
+        let mut go = Go {
+            max_playouts: None,
+            max_time_in_millis: None,
+            infinite: false,
+            starttime: Instant::now(),
+            playouts_done: 0,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match *arg {
+                "playouts" => go.max_playouts = Some(parse_value(arg, args.next())?),
+                "movetime" => go.max_time_in_millis = Some(parse_value(arg, args.next())?),
+                "infinite" => go.infinite = true,
+                _ => return Err(format!("unknown parameter '{}' of go", arg)),
+            }
+        }
+        if !go.infinite && go.max_playouts.is_none() && go.max_time_in_millis.is_none() {
+            let budget = self.ai.budget();
+            go.max_playouts = budget.max_playouts();
+            go.max_time_in_millis = budget.max_time_in_millis().map(|millis| millis as u32);
+        }
+
+        if self.session.outcome().is_some() {
+            return Ok(vec!["bestmove none".to_string()]);
+        }
+        let state = self.state().clone();
+        let reusable = match &mut self.search {
+            Some(search) => search.advance_to(&state),
+            None => false,
+        };
+        if !reusable {
+            self.search = Some(self.ai.search(&state).map_err(|e| e.to_string())?);
+        }
+        self.go = Some(go);
+        Ok(vec![])
+    }
+
+    fn set_option(&mut self, args: &[&str]) -> Result<Vec<String>, String> {
+        let (name, value) = match args {
+            ["name", name, "value", value @ ..] if !value.is_empty() => (*name, value.join(" ")),
+            _ => return Err("expected 'setoption name <name> value <value>'".to_string()),
+        };
+        let value = value.as_str();
+        match name.to_lowercase().as_str() {
+            "difficulty" => self
+                .ai
+                .set_difficulty(parse_variant(name, value, &Difficulty::ALL)?),
+            "algorithm" => self
+                .ai
+                .set_algorithm(parse_variant(name, value, &Algorithm::ALL)?),
+            "explorationconstant" => self
+                .ai
+                .set_exploration_constant(parse_value(name, Some(&value))?),
+            "maxdepth" => self.ai.set_max_depth(parse_value(name, Some(&value))?),
+            "evaluator" => {
+                self.ai
+                    .set_evaluator_kind(parse_variant(name, value, &EvaluatorKind::ALL)?)
+            }
+            "playoutpolicy" => self.ai.set_playout_policy_kind(parse_variant(
+                name,
+                value,
+                &PlayoutPolicyKind::ALL,
+            )?),
+            "reusesearch" => self.ai.set_reuse_search(parse_value(name, Some(&value))?),
+            "seed" => self.ai.set_seed(parse_value(name, Some(&value))?),
+            _ => return Err(format!("unknown option '{}'", name)),
+        }
+        // The search was made with the old settings
+        self.search = None;
+        Ok(vec![])
+    }
+
+    fn set_rules(&mut self, args: &[&str]) -> Result<Vec<String>, String> {
+        let tag = match args {
+            [tag] => tag,
+            _ => return Err("expected 'rules <rules>', e.g. 'rules L8'".to_string()),
+        };
+        self.rules = parse_rules(tag).map_err(|e| e.to_string())?;
+        self.session = GameSession::new(&GameState::new(&self.rules));
+        self.search = None;
+        Ok(vec![])
+    }
+
+    fn set_position(&mut self, args: &[&str]) -> Result<Vec<String>, String> {
+        let moves_index = args
+            .iter()
+            .position(|arg| *arg == "moves")
+            .unwrap_or(args.len());
+        let state = match &args[..moves_index] {
+            ["startpos"] => GameState::new(&self.rules),
+            ["notation", notation @ ..] if !notation.is_empty() => {
+                let notation = notation.join(" ");
+                GameState::from_notation(&notation)
+                    .map_err(|e| format!("invalid position '{}': {}", notation, e))?
+            }
+            _ => {
+                return Err(
+                    "expected 'position startpos|notation <position> [moves <move> ...]'"
+                        .to_string(),
+                )
+            }
+        };
+        let mut session = GameSession::new(&state);
+        play_moves(&mut session, args.get(moves_index + 1..).unwrap_or(&[]))?;
+        self.session = session;
+        Ok(vec![])
+    }
+
+    fn make_moves(&mut self, moves: &[&str]) -> Result<Vec<String>, String> {
+        // All or nothing, the position is unchanged if a move is illegal
+        let mut session = self.session.clone();
+        play_moves(&mut session, moves)?;
+        self.session = session;
+        Ok(vec![])
+    }
+
+    fn describe_position(&self) -> Vec<String> {
+        let state = self.state();
+        let legal_moves: Vec<String> = state
+            .legal_moves()
+            .filter_map(|(position, _)| state.move_to_notation(position).ok())
+            .collect();
+        vec![
+            format!("position {}", state.to_notation()),
+            format!("legalmoves {}", legal_moves.join(" "))
+                .trim_end()
+                .to_string(),
+            format!("result {}", result_of(self.session.outcome())),
+        ]
+    }
+}
+
+fn identify() -> Vec<String> {
+    let mut lines = vec![
+        format!("id name {}", ENGINE_NAME),
+        format!("id author {}", ENGINE_AUTHOR),
+    ];
+    lines.extend(
+        OPTIONS
+            .iter()
+            .map(|(name, kind)| format!("option name {} {}", name, kind)),
+    );
+    lines.push("boaok".to_string());
+    lines
+}
+
+/// Makes the moves given in move notation.
+fn play_moves(session: &mut GameSession, moves: &[&str]) -> Result<(), String> {
+    for notation in moves {
+        let position = session
+            .current_state_ref()
+            .move_from_notation(notation)
+            .map_err(|e| e.to_string())?;
+        session
+            .make_move(position)
+            .map_err(|e| format!("illegal move '{}' ({:?})", notation, e))?;
+    }
+    Ok(())
+}
+
+fn parse_value<T: std::str::FromStr>(name: &str, value: Option<&&str>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value of '{}'", name))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value '{}' of '{}'", value, name))
+}
+
+/// The variant whose name is `value` (ignoring the case), e.g. of `Difficulty::ALL`. The error
+/// names the `kind` of the value and the possible values.
+pub fn parse_variant<T: Debug + Copy>(
+    kind: &str,
+    value: &str,
+    variants: &[T],
+) -> Result<T, String> {
+    variants
+        .iter()
+        .copied()
+        .find(|variant| format!("{:?}", variant).eq_ignore_ascii_case(value))
+        .ok_or_else(|| {
+            let names: Vec<String> = variants
+                .iter()
+                .map(|variant| format!("{:?}", variant).to_lowercase())
+                .collect();
+            format!(
+                "unknown {} '{}', expected one of {}",
+                kind,
+                value,
+                names.join(", ")
+            )
+        })
+}
@@ -0,0 +1,99 @@
+use crate::{GameState, Outcome};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// Positions which occurred in a game so far, used to detect draws by repetition.
+#[derive(Clone, Default)]
+pub struct PositionHistory {
+    /// All positions in the order in which they occurred.
+    states: Vec<GameState>,
+    /// Indices into `states` of the positions with the same hash.
+    indices: HashMap<u64, Vec<usize>>,
+}
+
+impl PositionHistory {
+    pub fn new() -> Self {
+        PositionHistory::default()
+    }
+
+    /// History of a game with the given positions (in the order of the game).
+    pub fn from_states<'a>(states: impl IntoIterator<Item = &'a GameState>) -> Self {
+        let mut history = PositionHistory::new();
+        for state in states {
+            history.push(state);
+        }
+        history
+    }
+
+    /// Adds the position after a move. Returns how often the position occurred so far,
+    /// including this occurrence.
+    pub fn push(&mut self, state: &GameState) -> u8 {
+        let occurrences = self.occurrences(state).saturating_add(1);
+        self.indices
+            .entry(state.hash())
+            .or_default()
+            .push(self.states.len());
+        self.states.push(state.clone());
+        occurrences
+    }
+
+    /// Removes the latest position (e.g. when a move is taken back).
+    pub fn pop(&mut self) {
+        if let Some(state) = self.states.pop() {
+            if let Some(indices) = self.indices.get_mut(&state.hash()) {
+                indices.pop();
+                if indices.is_empty() {
+                    self.indices.remove(&state.hash());
+                }
+            }
+        }
+    }
+
+    /// How often the position of `state` occurred. Positions with the same hash are compared
+    /// with `is_same_position`, since different positions may have the same hash.
+    pub fn occurrences(&self, state: &GameState) -> u8 {
+        let occurrences = match self.indices.get(&state.hash()) {
+            Some(indices) => indices
+                .iter()
+                .filter(|index| is_same_position(&self.states[**index], state))
+                .count(),
+            None => 0,
+        };
+        u8::try_from(occurrences).unwrap_or(u8::MAX)
+    }
+
+    /// Result of the game if `state` is the latest position: the outcome of the state itself
+    /// (see `GameState::outcome`) or a draw if the position occurred as often as the rules
+    /// allow.
+    pub fn outcome(&self, state: &GameState) -> Option<Outcome> {
+        state.outcome().or_else(|| {
+            let repetitions_for_draw = state.rules().repetitions_for_draw();
+            if repetitions_for_draw > 0 && self.occurrences(state) >= repetitions_for_draw {
+                Some(Outcome::Draw)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Number of positions in the history.
+    pub fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.states.is_empty()
+    }
+}
+
+/// True if both states have the same rules, cells and player to move. The plies without
+/// capture are not compared, they differ between the repetitions of a position.
+fn is_same_position(state: &GameState, other: &GameState) -> bool {
+    state.rules() == other.rules()
+        && state.curr_player == other.curr_player
+        && state
+            .fields_of_players
+            .iter()
+            .zip(other.fields_of_players.iter())
+            .all(|(field, other_field)| field.cells == other_field.cells)
+}
@@ -1,14 +1,28 @@
-#![feature(test)]
-
-mod ai;
+#![cfg_attr(test, feature(test))]
+
+pub mod ai;
+pub mod engine;
+pub mod history;
+pub mod notation;
+pub mod record;
+pub mod session;
+pub mod setup;
+pub mod tournament;
+pub mod trace;
+pub mod zobrist;
 
 extern crate console_error_panic_hook;
 // mod ai;
 
 use std::fmt::{Display, Formatter};
 
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
 use wasm_bindgen::prelude::*;
 
+use crate::setup::{StartingLayout, MAX_STONES_PER_PLAYER};
+use crate::trace::MoveTrace;
+
 #[wasm_bindgen]
 extern "C" {
     pub fn alert(s: &str);
@@ -19,62 +33,210 @@ pub fn greet(name: &str) {
     alert(&format!("Hello, {}!", name));
 }
 
-const LENGTH_OF_FIELD: usize = 8;
+/// Number of cells in a row of the standard board.
+pub const DEFAULT_LENGTH_OF_FIELD: usize = 8;
+/// Smallest supported number of cells in a row.
+pub const MIN_LENGTH_OF_FIELD: usize = 4;
+/// Largest supported number of cells in a row.
+pub const MAX_LENGTH_OF_FIELD: usize = 10;
 
 #[repr(u8)]
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum GameError {
     PositionOutsideField,
     CellEmpty,
     NoMoveFound,
     RepeatingPosition,
+    InvalidRuleSet,
+    InvalidSetup,
+}
+
+impl Display for GameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Result of a finished game.
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum Outcome {
+    Player0Wins,
+    Player1Wins,
+    /// The game was drawn by repetition or by the limit of moves without capture.
+    Draw,
+}
+
+impl Outcome {
+    /// Outcome of a game won by `player` (0 or 1).
+    pub fn win_of(player: u8) -> Self {
+        if player == 0 {
+            Outcome::Player0Wins
+        } else {
+            Outcome::Player1Wins
+        }
+    }
+
+    /// The winning player or `None` for a draw.
+    pub fn winner(self) -> Option<u8> {
+        match self {
+            Outcome::Player0Wins => Some(0),
+            Outcome::Player1Wins => Some(1),
+            Outcome::Draw => None,
+        }
+    }
+}
+
+/// Default number of occurrences of a position after which the game is drawn.
+pub const DEFAULT_REPETITIONS_FOR_DRAW: u8 = 3;
+/// Default number of plies without any capture after which the game is drawn.
+pub const DEFAULT_MAX_PLIES_WITHOUT_CAPTURE: u32 = 200;
+
+/// Rules of the game which can be chosen when a game is started.
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleSet {
+    /// Number of cells in a row. Every player owns two rows.
+    length_of_field: usize,
+    /// The game is drawn when the same position (including the player to move) occurs this
+    /// often. 0 disables the rule.
+    repetitions_for_draw: u8,
+    /// The game is drawn after this many plies in a row without any capture. 0 disables the
+    /// rule.
+    max_plies_without_capture: u32,
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            length_of_field: DEFAULT_LENGTH_OF_FIELD,
+            repetitions_for_draw: DEFAULT_REPETITIONS_FOR_DRAW,
+            max_plies_without_capture: DEFAULT_MAX_PLIES_WITHOUT_CAPTURE,
+        }
+    }
+}
+
+impl RuleSet {
+    /// Rules for a board with `length_of_field` cells per row (between `MIN_LENGTH_OF_FIELD`
+    /// and `MAX_LENGTH_OF_FIELD`) and the default draw rules.
+    pub fn new(length_of_field: usize) -> Result<Self, GameError> {
+        if !(MIN_LENGTH_OF_FIELD..=MAX_LENGTH_OF_FIELD).contains(&length_of_field) {
+            return Err(GameError::InvalidRuleSet);
+        }
+        Ok(RuleSet {
+            length_of_field,
+            ..RuleSet::default()
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl RuleSet {
+    #[wasm_bindgen(constructor)]
+    pub fn new_wasm(length_of_field: usize) -> Result<RuleSet, JsValue> {
+        RuleSet::new(length_of_field).map_err(|e| {
+            JsValue::from(
+                format!(
+                    "Invalid rule set: {:?}, the length of the field must be between {} and {}",
+                    e, MIN_LENGTH_OF_FIELD, MAX_LENGTH_OF_FIELD
+                )
+                .as_str(),
+            )
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn length_of_field(&self) -> usize {
+        self.length_of_field
+    }
+
+    /// Number of cells of one player (two rows).
+    #[wasm_bindgen(getter)]
+    pub fn number_of_cells(&self) -> usize {
+        2 * self.length_of_field
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn repetitions_for_draw(&self) -> u8 {
+        self.repetitions_for_draw
+    }
+
+    /// The game is drawn when a position occurs `repetitions_for_draw` times (0 disables the
+    /// rule).
+    pub fn set_repetitions_for_draw(&mut self, repetitions_for_draw: u8) {
+        self.repetitions_for_draw = repetitions_for_draw;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_plies_without_capture(&self) -> u32 {
+        self.max_plies_without_capture
+    }
+
+    /// The game is drawn after `max_plies_without_capture` plies without capture (0 disables
+    /// the rule).
+    pub fn set_max_plies_without_capture(&mut self, max_plies_without_capture: u32) {
+        self.max_plies_without_capture = max_plies_without_capture;
+    }
 }
 
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum Direction {
     Left,
     Right,
 }
 
 #[wasm_bindgen]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct MoveStatistic {
     pub last_move_position: usize,
     pub stones_of_opponent_removed: u8,
 }
 
-impl Default for MoveStatistic {
-    fn default() -> Self {
-        MoveStatistic {
-            last_move_position: 0,
-            stones_of_opponent_removed: 0,
-        }
+#[wasm_bindgen]
+impl MoveStatistic {
+    /// The statistic as plain JS object.
+    pub fn to_js(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(self).map_err(JsValue::from)
+    }
+
+    /// Statistic from a plain JS object created by `to_js`.
+    pub fn from_js(value: JsValue) -> Result<MoveStatistic, JsValue> {
+        serde_wasm_bindgen::from_value(value).map_err(JsValue::from)
     }
 }
 
 #[wasm_bindgen]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OnePlayersField {
     /// direction of movement
     direction: Direction,
     /// Number of stones in the own half of the field.
-    cells: [u8; 2 * LENGTH_OF_FIELD],
+    cells: Vec<u8>,
 }
 
 /// State of the game
 #[wasm_bindgen]
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedGameState")]
 pub struct GameState {
     /// The half's of the boards belonging to each player
     // pub fields_of_players: Vec<OnePlayersField>,
     fields_of_players: [OnePlayersField; 2],
+    /// Rules of the game, e.g., the size of the board
+    rules: RuleSet,
     /// Players: 0 or 1
     pub curr_player: u8,
     /// Game over?
     pub game_over: bool,
     /// Statistics after last move
     pub move_statistic: MoveStatistic,
+    /// Number of plies since the last capture (or since the beginning of the game)
+    plies_without_capture: u32,
+    /// Zobrist hash of the position, see `zobrist`
+    #[serde(skip)]
+    hash: u64,
 }
 
 #[wasm_bindgen]
@@ -84,21 +246,22 @@ pub fn init_panic_hook() {
 
 #[wasm_bindgen]
 impl OnePlayersField {
-    fn new(direction: Direction) -> Self {
-        OnePlayersField {
-            direction: direction,
-            cells: [2; 2 * LENGTH_OF_FIELD],
-        }
+    fn new(direction: Direction, cells: Vec<u8>) -> Self {
+        OnePlayersField { direction, cells }
     }
 
     /// Return new distribution of stones on the field and the position of the stone which was
-    /// put last.
-    fn make_move_from(&self, position: usize) -> Result<(Self, usize), GameError> {
-        if position > self.cells.len() {
+    /// put last. Every drop and pick-up is recorded in `trace` if given.
+    fn make_move_from(
+        &self,
+        position: usize,
+        mut trace: Option<&mut MoveTrace>,
+    ) -> Result<(Self, usize), GameError> {
+        if position >= self.cells.len() {
             return Err(GameError::PositionOutsideField);
         }
 
-        if self.cells[position] <= 0 {
+        if self.cells[position] == 0 {
             return Err(GameError::CellEmpty);
         }
 
@@ -110,11 +273,14 @@ impl OnePlayersField {
             curr_pos = if self.direction == Direction::Right {
                 (curr_pos + 1) % self.cells.len()
             } else {
-                (curr_pos + (2 * LENGTH_OF_FIELD - 1)) % self.cells.len() // corresponds to make a step "backward"
+                (curr_pos + (self.cells.len() - 1)) % self.cells.len() // corresponds to make a step "backward"
             };
 
             new_field_state[curr_pos] += 1;
             stones_in_the_hand -= 1;
+            if let Some(trace) = trace.as_mut() {
+                trace.record_drop(curr_pos);
+            }
             if (stones_in_the_hand == 0) && (new_field_state[curr_pos] == 1) {
                 break;
             } else if (stones_in_the_hand == 0) && (new_field_state[curr_pos] > 1) {
@@ -129,52 +295,175 @@ impl OnePlayersField {
 
                 stones_in_the_hand = new_field_state[curr_pos];
                 new_field_state[curr_pos] = 0;
+                if let Some(trace) = trace.as_mut() {
+                    trace.record_pickup(curr_pos);
+                }
             }
         }
 
-        return Ok((
+        Ok((
             OnePlayersField {
                 direction: self.direction,
                 cells: new_field_state,
             },
             curr_pos,
-        ));
+        ))
+    }
+}
+
+impl OnePlayersField {
+    fn number_of_stones(&self) -> u32 {
+        self.cells.iter().map(|stones| *stones as u32).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.cells.iter().all(|stones| *stones == 0)
+    }
+}
+
+/// Deserialized game state which still has to be checked, see `GameState::try_from`.
+#[derive(Deserialize)]
+struct UncheckedGameState {
+    fields_of_players: [OnePlayersField; 2],
+    rules: RuleSet,
+    curr_player: u8,
+    move_statistic: MoveStatistic,
+    #[serde(default)]
+    plies_without_capture: u32,
+}
+
+impl TryFrom<UncheckedGameState> for GameState {
+    type Error = GameError;
+
+    /// Accepts only states which can occur in a game with valid rules: every player owns the
+    /// right number of cells, moves in his/her direction and has at most
+    /// `MAX_STONES_PER_PLAYER` stones. Whether the game is over is derived from the cells.
+    fn try_from(unchecked: UncheckedGameState) -> Result<Self, Self::Error> {
+        RuleSet::new(unchecked.rules.length_of_field)?;
+        let rules = unchecked.rules;
+        if unchecked.curr_player > 1 {
+            return Err(GameError::InvalidSetup);
+        }
+        for (field, direction) in unchecked
+            .fields_of_players
+            .iter()
+            .zip([Direction::Left, Direction::Right].iter())
+        {
+            if field.direction != *direction
+                || field.cells.len() != rules.number_of_cells()
+                || field.number_of_stones() > MAX_STONES_PER_PLAYER
+            {
+                return Err(GameError::InvalidSetup);
+            }
+        }
+
+        let mut state = GameState {
+            fields_of_players: unchecked.fields_of_players,
+            rules,
+            curr_player: unchecked.curr_player,
+            game_over: false,
+            move_statistic: unchecked.move_statistic,
+            plies_without_capture: unchecked.plies_without_capture,
+            hash: 0,
+        };
+        state.game_over = state.outcome().is_some();
+        state.hash = zobrist::full_hash(&state);
+        Ok(state)
     }
 }
 
 impl Default for GameState {
     fn default() -> Self {
-        GameState {
-            fields_of_players: [
-                OnePlayersField::new(Direction::Left),
-                OnePlayersField::new(Direction::Right),
-            ],
-            curr_player: 0,
-            game_over: false,
-            move_statistic: MoveStatistic::default(),
-        }
+        GameState::new(&RuleSet::default())
     }
 }
 
 #[wasm_bindgen]
 impl GameState {
+    /// Beginning of a game with the given rules and the standard layout (2 stones per cell).
     #[wasm_bindgen(constructor)]
-    pub fn new() -> GameState {
-        GameState::default()
+    pub fn new(rules: &RuleSet) -> GameState {
+        GameState::with_layout(rules, &StartingLayout::default())
+            .expect("The default layout fits to every board.")
     }
 
-    fn game_over_internal(&self, fields: &[OnePlayersField]) -> bool {
-        (fields[0].cells.iter().sum::<u8>() == 0) || (fields[1].cells.iter().sum::<u8>() == 0)
+    /// Beginning of a game with a named layout, see `StartingLayout::from_name`.
+    pub fn from_layout(rules: &RuleSet, layout_name: &str) -> Result<GameState, JsValue> {
+        StartingLayout::from_name(layout_name)
+            .and_then(|layout| GameState::with_layout(rules, &layout))
+            .map_err(|e| {
+                JsValue::from(format!("Invalid layout '{}': {:?}", layout_name, e).as_str())
+            })
+    }
+
+    /// Beginning of a game with stones arranged by the players. The cells are given in the
+    /// internal order of each player and every player has to place exactly `stones_per_player`
+    /// stones.
+    pub fn from_arranged_cells(
+        rules: &RuleSet,
+        cells_of_player0: Vec<u8>,
+        cells_of_player1: Vec<u8>,
+        stones_per_player: u32,
+    ) -> Result<GameState, JsValue> {
+        let layout = StartingLayout::Arranged {
+            cells: [cells_of_player0, cells_of_player1],
+            stones_per_player,
+        };
+        GameState::with_layout(rules, &layout)
+            .map_err(|e| JsValue::from(format!("Invalid arrangement of stones: {:?}", e).as_str()))
+    }
+
+    /// The state as plain JS object, e.g. to store it in `localStorage`.
+    pub fn to_js(&self) -> Result<JsValue, JsValue> {
+        serde_wasm_bindgen::to_value(self).map_err(JsValue::from)
+    }
+
+    /// State from a plain JS object created by `to_js`. Fails if the object is not a valid state.
+    pub fn from_js(value: JsValue) -> Result<GameState, JsValue> {
+        serde_wasm_bindgen::from_value(value).map_err(JsValue::from)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rules(&self) -> RuleSet {
+        self.rules
     }
 
     pub fn game_over(&self) -> bool {
-        self.winner().is_some()
+        self.outcome().is_some()
+    }
+
+    /// Result of the game if it is over: a player wins when the opponent has no stones left, it
+    /// is a draw when the limit of plies without capture is reached. A draw by repetition
+    /// depends on the previous positions and is detected by `history::PositionHistory`.
+    pub fn outcome(&self) -> Option<Outcome> {
+        if let Some(winner) = self.winner() {
+            return Some(Outcome::win_of(winner));
+        }
+        let max_plies_without_capture = self.rules.max_plies_without_capture;
+        if max_plies_without_capture > 0 && self.plies_without_capture >= max_plies_without_capture
+        {
+            return Some(Outcome::Draw);
+        }
+        None
+    }
+
+    /// Number of plies since the last capture (or since the beginning of the game).
+    #[wasm_bindgen(getter)]
+    pub fn plies_without_capture(&self) -> u32 {
+        self.plies_without_capture
+    }
+
+    /// Zobrist hash of the position (the stones of both players, the length of the field and the
+    /// player to move). Equal positions have equal hashes, different positions have equal hashes
+    /// only very rarely.
+    pub fn hash(&self) -> u64 {
+        self.hash
     }
 
     fn winner(&self) -> Option<u8> {
-        if self.fields_of_players[0].cells.iter().sum::<u8>() == 0 {
+        if self.fields_of_players[0].is_empty() {
             Some(1)
-        } else if self.fields_of_players[1].cells.iter().sum::<u8>() == 0 {
+        } else if self.fields_of_players[1].is_empty() {
             Some(0)
         } else {
             None
@@ -190,66 +479,95 @@ impl GameState {
 
     /// Makes a move for the current user and returns a copy of the new GameState.
     fn make_move(&self, position: usize) -> Result<GameState, GameError> {
+        self.make_move_with_trace(position, None)
+    }
+
+    /// Same as `make_move`, but records the course of the move in `trace` if given.
+    fn make_move_with_trace(
+        &self,
+        position: usize,
+        mut trace: Option<&mut MoveTrace>,
+    ) -> Result<GameState, GameError> {
         let other_player: usize = (self.curr_player as usize + 1) % 2;
         let curr_player: usize = self.curr_player as usize;
 
-        let new_field_state_of_current_player =
-            self.fields_of_players[curr_player].make_move_from(position);
-
-        match new_field_state_of_current_player {
-            Err(e) => return Err(e),
-            Ok(new_field_and_last_location_curr_user) => {
-                // Now, remove stones from the other player if needed
-                let mut other_player_field = self.fields_of_players[other_player].clone();
-                let mut removed_stones_of_opponent: u8 = 0;
-
-                if new_field_and_last_location_curr_user.1 < LENGTH_OF_FIELD {
-                    if self.fields_of_players[other_player].cells
-                        [new_field_and_last_location_curr_user.1]
-                        > 0
-                    {
-                        // remove stones
-                        removed_stones_of_opponent +=
-                            other_player_field.cells[new_field_and_last_location_curr_user.1];
-                        removed_stones_of_opponent += other_player_field.cells
-                            [2 * LENGTH_OF_FIELD - new_field_and_last_location_curr_user.1 - 1];
-
-                        other_player_field.cells[new_field_and_last_location_curr_user.1] = 0;
-                        other_player_field.cells
-                            [2 * LENGTH_OF_FIELD - new_field_and_last_location_curr_user.1 - 1] = 0;
-                    }
-                }
-
-                let fields_of_both_players = if curr_player == 0 {
-                    [
-                        new_field_and_last_location_curr_user.0.clone(),
-                        other_player_field,
-                    ]
-                } else {
-                    [
-                        other_player_field,
-                        new_field_and_last_location_curr_user.0.clone(),
-                    ]
-                };
-
-                let move_statistic = MoveStatistic {
-                    stones_of_opponent_removed: removed_stones_of_opponent,
-                    last_move_position: new_field_and_last_location_curr_user.1,
-                };
-
-                let game_over = self.game_over_internal(&fields_of_both_players);
-                Ok(GameState {
-                    curr_player: other_player as u8,
-                    fields_of_players: fields_of_both_players,
-                    game_over: game_over,
-                    move_statistic: move_statistic,
-                })
+        let new_field_and_last_location_curr_user =
+            self.fields_of_players[curr_player].make_move_from(position, trace.as_deref_mut())?;
+
+        // Now, remove stones from the other player if needed
+        let length_of_field = self.rules.length_of_field;
+        let mut other_player_field = self.fields_of_players[other_player].clone();
+        let mut removed_stones_of_opponent: u8 = 0;
+
+        if new_field_and_last_location_curr_user.1 < length_of_field
+            && self.fields_of_players[other_player].cells[new_field_and_last_location_curr_user.1]
+                > 0
+        {
+            // remove stones
+            removed_stones_of_opponent +=
+                other_player_field.cells[new_field_and_last_location_curr_user.1];
+            removed_stones_of_opponent += other_player_field.cells
+                [2 * length_of_field - new_field_and_last_location_curr_user.1 - 1];
+
+            other_player_field.cells[new_field_and_last_location_curr_user.1] = 0;
+            other_player_field.cells
+                [2 * length_of_field - new_field_and_last_location_curr_user.1 - 1] = 0;
+            if let Some(trace) = trace {
+                trace.record_capture(new_field_and_last_location_curr_user.1);
+                trace.record_capture(
+                    2 * length_of_field - new_field_and_last_location_curr_user.1 - 1,
+                );
             }
         }
+
+        let fields_of_both_players = if curr_player == 0 {
+            [
+                new_field_and_last_location_curr_user.0.clone(),
+                other_player_field,
+            ]
+        } else {
+            [
+                other_player_field,
+                new_field_and_last_location_curr_user.0.clone(),
+            ]
+        };
+
+        let move_statistic = MoveStatistic {
+            stones_of_opponent_removed: removed_stones_of_opponent,
+            last_move_position: new_field_and_last_location_curr_user.1,
+        };
+
+        let plies_without_capture = if removed_stones_of_opponent > 0 {
+            0
+        } else {
+            self.plies_without_capture + 1
+        };
+        let mut hash = self.hash
+            ^ zobrist::side_to_move_key(self.curr_player)
+            ^ zobrist::side_to_move_key(other_player as u8);
+        for (player, (old_field, new_field)) in self
+            .fields_of_players
+            .iter()
+            .zip(fields_of_both_players.iter())
+            .enumerate()
+        {
+            hash = zobrist::update_cells(hash, player, &old_field.cells, &new_field.cells);
+        }
+        let mut state = GameState {
+            curr_player: other_player as u8,
+            fields_of_players: fields_of_both_players,
+            rules: self.rules,
+            game_over: false,
+            move_statistic,
+            plies_without_capture,
+            hash,
+        };
+        state.game_over = state.outcome().is_some();
+        Ok(state)
     }
 
     pub fn make_move_wasm(&self, position: usize) -> Result<GameState, JsValue> {
-        if position >= 2 * LENGTH_OF_FIELD {
+        if position >= self.rules.number_of_cells() {
             return Err(JsValue::from("Position outside of field"));
         }
         let new_state = self.make_move(position);
@@ -261,28 +579,11 @@ impl GameState {
         }
     }
 
-    fn get_position_from_row_col(&self, player: usize, row: usize, col: usize) -> usize {
-        let position = if player == 0 {
-            row * LENGTH_OF_FIELD + (LENGTH_OF_FIELD - 1 - col)
-        } else {
-            row * LENGTH_OF_FIELD + (col)
-        };
-
-        position
-    }
-
-    fn make_move_via_row_col(&self, row: usize, col: usize) -> Result<GameState, GameError> {
-        if col >= LENGTH_OF_FIELD {
-            return Err(GameError::PositionOutsideField);
-        }
-
-        let position = self.get_position_from_row_col(self.curr_player as usize, row, col);
-        self.make_move(position)
-    }
-
     /// Makes move using row and column notation.
-    /// Row and column are always relative to the user. I.e, it looks from his/her position on the board and the bottom row is the 0th, and top row is top 1st one.
+    /// Row and column are always relative to the user. I.e, it looks from his/her position on the board and the bottom row (the back row) is the 0th, and top row (the front row) is the 1st one.
     /// The columns are counted from left to right.
+    /// Before the move notation was added, row 0 was the front row of both players, see
+    /// `get_position_from_row_col`.
     pub fn make_move_via_row_col_wasm(&self, row: usize, col: usize) -> Result<GameState, JsValue> {
         let new_state = self.make_move_via_row_col(row, col);
         match new_state {
@@ -296,26 +597,150 @@ impl GameState {
     /// Returns the number of stones at specified position.
     /// Error is thrown is position is outside of the field or if wrong player ID is provided.
     pub fn get_number_stones_at(&self, player: usize, position: usize) -> Result<u8, JsValue> {
-        assert!(position < 2 * LENGTH_OF_FIELD);
+        assert!(position < self.rules.number_of_cells());
         assert!(player < 2);
         Ok(self.fields_of_players[player].cells[position])
     }
 
+    /// Returns the positions of all legal moves of the current player.
+    pub fn legal_moves_wasm(&self) -> Vec<usize> {
+        self.legal_moves().map(|(position, _)| position).collect()
+    }
+
+    pub fn is_legal_move(&self, position: usize) -> bool {
+        self.outcome().is_none() && self.make_move(position).is_ok()
+    }
+
     pub fn render(&self) -> String {
         self.to_string()
     }
 }
 
+impl GameState {
+    /// Beginning of a game with the given rules and layout. Fails if the layout is invalid, see
+    /// `StartingLayout::cells`.
+    pub fn with_layout(rules: &RuleSet, layout: &StartingLayout) -> Result<GameState, GameError> {
+        let [cells_of_player0, cells_of_player1] = layout.cells(rules)?;
+        let mut state = GameState {
+            fields_of_players: [
+                OnePlayersField::new(Direction::Left, cells_of_player0),
+                OnePlayersField::new(Direction::Right, cells_of_player1),
+            ],
+            rules: *rules,
+            curr_player: 0,
+            game_over: false,
+            move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
+        };
+        state.hash = zobrist::full_hash(&state);
+        Ok(state)
+    }
+
+    /// Makes a move using row and column notation, see `make_move_via_row_col_wasm`.
+    pub fn make_move_via_row_col(&self, row: usize, col: usize) -> Result<GameState, GameError> {
+        if row >= 2 || col >= self.rules.length_of_field {
+            return Err(GameError::PositionOutsideField);
+        }
+
+        let position = self.get_position_from_row_col(self.curr_player as usize, row, col);
+        self.make_move(position)
+    }
+
+    /// Position on the field of `player` for a row and column relative to the player, see
+    /// `make_move_via_row_col_wasm`. Row 0 is the back row (the positions from
+    /// `length_of_field` on) and row 1 the front row.
+    ///
+    /// This mapping changed for both players with the move notation. Before, row 0 was the
+    /// front row: row 0, column 0 was position 7 for player 0 and position 0 for player 1.
+    /// Now it is position 15 for player 0 and 8 for player 1 (on a field of length 8).
+    pub fn get_position_from_row_col(&self, player: usize, row: usize, col: usize) -> usize {
+        let length_of_field = self.rules.length_of_field;
+        match (player, row) {
+            (0, 0) => 2 * length_of_field - 1 - col,
+            (0, _) => col,
+            (_, 0) => length_of_field + col,
+            (_, _) => length_of_field - 1 - col,
+        }
+    }
+
+    /// Row and column relative to `player` of a position on the field of the player (inverse of
+    /// `get_position_from_row_col`).
+    pub fn get_row_col_from_position(&self, player: usize, position: usize) -> (usize, usize) {
+        let length_of_field = self.rules.length_of_field;
+        let front_row = position < length_of_field;
+        match (player, front_row) {
+            (0, true) => (1, position),
+            (0, false) => (0, 2 * length_of_field - 1 - position),
+            (_, true) => (1, length_of_field - 1 - position),
+            (_, false) => (0, position - length_of_field),
+        }
+    }
+
+    /// Number of stones of a player.
+    pub fn stones_of_player(&self, player: usize) -> u32 {
+        self.fields_of_players[player].number_of_stones()
+    }
+
+    /// Positions of the current player which contain stones. Every legal move starts at one of
+    /// these positions, but a move from such a position is still illegal if it would result
+    /// in `GameError::RepeatingPosition`.
+    pub fn occupied_cells(&self) -> impl Iterator<Item = usize> + '_ {
+        self.fields_of_players[self.curr_player as usize]
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|(_, stones_in_cell)| **stones_in_cell > 0)
+            .map(|(cell_id, _)| cell_id)
+    }
+
+    /// Iterates over all legal moves of the current player together with the resulting game
+    /// states. There are no legal moves if the game is over.
+    pub fn legal_moves(&self) -> LegalMoves<'_> {
+        LegalMoves {
+            state: self,
+            next_position: if self.outcome().is_some() {
+                self.rules.number_of_cells()
+            } else {
+                0
+            },
+        }
+    }
+}
+
+/// Iterator over the legal moves of a game state, see `GameState::legal_moves`.
+pub struct LegalMoves<'a> {
+    state: &'a GameState,
+    next_position: usize,
+}
+
+impl Iterator for LegalMoves<'_> {
+    /// Position of the move and the game state after the move.
+    type Item = (usize, GameState);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next_position < self.state.rules.number_of_cells() {
+            let position = self.next_position;
+            self.next_position += 1;
+            if let Ok(newstate) = self.state.make_move(position) {
+                return Some((position, newstate));
+            }
+        }
+        None
+    }
+}
+
 impl Display for GameState {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let length_of_field = self.rules.length_of_field;
         match f.write_str(format!("Current player: {}, stones removed in last move: {}, game over: {}, player 1 stones: {}, player 2 stones: {}\n{:?}\n{:?} Player 2\n------------------------------\n{:?}  Player 1\n{:?}",
                             self.curr_player,  self.move_statistic.stones_of_opponent_removed, self.game_over, // some statistics
-                            self.fields_of_players[0].cells.iter().sum::<u8>(), self.fields_of_players[1].cells.iter().sum::<u8>(),
+                            self.stones_of_player(0), self.stones_of_player(1),
 
-                            self.fields_of_players[1].cells[LENGTH_OF_FIELD..(2*LENGTH_OF_FIELD)].iter().rev().collect::<Vec<&u8>>(),
-                            self.fields_of_players[1].cells[0..LENGTH_OF_FIELD].to_vec(),
-                            self.fields_of_players[0].cells[0..LENGTH_OF_FIELD].to_vec(),
-                            self.fields_of_players[0].cells[LENGTH_OF_FIELD..(2*LENGTH_OF_FIELD)].iter().rev().collect::<Vec<&u8>>(),
+                            self.fields_of_players[1].cells[length_of_field..(2*length_of_field)].iter().rev().collect::<Vec<&u8>>(),
+                            self.fields_of_players[1].cells[0..length_of_field].to_vec(),
+                            self.fields_of_players[0].cells[0..length_of_field].to_vec(),
+                            self.fields_of_players[0].cells[length_of_field..(2*length_of_field)].iter().rev().collect::<Vec<&u8>>(),
         ).as_str()
         ) {
             Ok(_) => Ok(()),
@@ -328,7 +753,11 @@ impl Display for GameState {
 mod tests {
     extern crate test;
 
-    use crate::{Direction, GameState,MoveStatistic, OnePlayersField,};
+    use crate::session::GameSession;
+    use crate::setup::StartingLayout;
+    use crate::{
+        Direction, GameError, GameState, MoveStatistic, OnePlayersField, Outcome, RuleSet,
+    };
     use wasm_bindgen_test::*;
 
     #[test]
@@ -336,12 +765,15 @@ mod tests {
         let gamestate1 = GameState {
             game_over: false,
             move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
             curr_player: 0,
+            rules: RuleSet::default(),
             fields_of_players: [
                 OnePlayersField {
                     // Player 1, moves to the right
                     direction: Direction::Right,
-                    cells: [
+                    cells: vec![
                         0, 0, 0, 1, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
                         0, 0, 0, 0, 0, 0, 0,
                         0, // 15 14 13 12 11 10 9 8 // corresponds to "0,0,0,0,1,0,0,0" when reversed
@@ -350,7 +782,7 @@ mod tests {
                 OnePlayersField {
                     // Player 2, moves to the left
                     direction: Direction::Left,
-                    cells: [
+                    cells: vec![
                         1, 0, 0, 0, 1, 0, 0, 0, // 15 14 13 12 11 10 9 8
                         0, 0, 0, 0, 0, 0, 0,
                         0, // 0   1  2  3  4  5 6 7   // corresponds to "0,0,0,1,0,0,0,0" when reversed
@@ -381,17 +813,21 @@ mod tests {
         );
     }
 
+    #[allow(clippy::bool_assert_comparison)]
     #[test]
     fn game_over_works() {
         let gamestate1 = GameState {
             game_over: false,
             move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
             curr_player: 0,
+            rules: RuleSet::default(),
             fields_of_players: [
                 OnePlayersField {
                     // Player 1, moves to the right
                     direction: Direction::Right,
-                    cells: [
+                    cells: vec![
                         0, 0, 0, 1, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
                         0, 0, 0, 1, 0, 0, 0, 0,
                     ], // 15 14 13 12 11 10 9 8 // corresponds to "0,0,0,0,1,0,0,0" when reversed
@@ -399,7 +835,7 @@ mod tests {
                 OnePlayersField {
                     // Player 2, moves to the left
                     direction: Direction::Left,
-                    cells: [
+                    cells: vec![
                         0, 0, 0, 0, 0, 0, 0, 0, // 15 14 13 12 11 10 9 8
                         0, 0, 0, 0, 0, 0, 0, 0,
                     ], // 0   1  2  3  4  5 6 7   // corresponds to "0,0,0,1,0,0,0,0" when reversed
@@ -410,12 +846,15 @@ mod tests {
         let gamestate2 = GameState {
             game_over: false,
             move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
             curr_player: 0,
+            rules: RuleSet::default(),
             fields_of_players: [
                 OnePlayersField {
                     // Player 1, moves to the right
                     direction: Direction::Right,
-                    cells: [
+                    cells: vec![
                         0, 0, 0, 0, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
                         0, 0, 0, 0, 0, 0, 0, 0,
                     ], // 15 14 13 12 11 10 9 8
@@ -423,7 +862,7 @@ mod tests {
                 OnePlayersField {
                     // Player 2, moves to the left
                     direction: Direction::Left,
-                    cells: [
+                    cells: vec![
                         0, 0, 0, 1, 0, 0, 0, 0, // 15 14 13 12 11 10 9 8
                         0, 0, 0, 0, 0, 0, 0, 0,
                     ], // 0   1  2  3  4  5 6 7
@@ -434,12 +873,15 @@ mod tests {
         let gamestate3 = GameState {
             game_over: false,
             move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
             curr_player: 0,
+            rules: RuleSet::default(),
             fields_of_players: [
                 OnePlayersField {
                     // Player 1, moves to the right
                     direction: Direction::Right,
-                    cells: [
+                    cells: vec![
                         0, 1, 0, 0, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
                         0, 0, 0, 0, 0, 0, 0, 0,
                     ], // 15 14 13 12 11 10 9 8
@@ -447,7 +889,7 @@ mod tests {
                 OnePlayersField {
                     // Player 2, moves to the left
                     direction: Direction::Left,
-                    cells: [
+                    cells: vec![
                         0, 0, 0, 1, 0, 0, 0, 0, // 15 14 13 12 11 10 9 8
                         0, 0, 0, 0, 0, 0, 0, 0,
                     ], // 0   1  2  3  4  5 6 7
@@ -460,17 +902,21 @@ mod tests {
         assert_eq!(gamestate3.game_over(), false);
     }
 
+    #[allow(clippy::bool_assert_comparison)]
     #[test]
     fn test_move_deletes_stone_of_opposite() {
         let gamestate = GameState {
             game_over: false,
             move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
             curr_player: 0,
+            rules: RuleSet::default(),
             fields_of_players: [
                 OnePlayersField {
                     // Player 1, moves to the right
                     direction: Direction::Right,
-                    cells: [
+                    cells: vec![
                         0, 0, 0, 1, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
                         0, 0, 0, 1, 0, 0, 0, 0,
                     ], // 15 14 13 12 11 10 9 8 // corresponds to "0,0,0,0,1,0,0,0" when reversed
@@ -478,7 +924,7 @@ mod tests {
                 OnePlayersField {
                     // Player 2, moves to the left
                     direction: Direction::Left,
-                    cells: [
+                    cells: vec![
                         0, 0, 0, 0, 1, 0, 0, 0, // 15 14 13 12 11 10 9 8
                         0, 0, 0, 0, 1, 0, 0, 0,
                     ], // 0   1  2  3  4  5 6 7   // corresponds to "0,0,0,1,0,0,0,0" when reversed
@@ -534,55 +980,2052 @@ mod tests {
         }
     }
 
-    use crate::ai::{AI, get_random_number, game_playout};
-    use self::test::Bencher;
+    #[test]
+    fn test_legal_moves() {
+        let game = GameState::default(); // beginning of the game
+        let legal_moves: Vec<(usize, GameState)> = game.legal_moves().collect();
+        assert_eq!(legal_moves.len(), 16);
+        for (position, newstate) in legal_moves.iter() {
+            assert_eq!(newstate.curr_player, 1);
+            assert_eq!(
+                newstate.fields_of_players[0].cells,
+                game.make_move(*position).ok().unwrap().fields_of_players[0].cells
+            );
+        }
+        assert_eq!(game.legal_moves_wasm(), (0..16).collect::<Vec<usize>>());
 
+        let gamestate = GameState {
+            game_over: false,
+            move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
+            curr_player: 0,
+            rules: RuleSet::default(),
+            fields_of_players: [
+                OnePlayersField {
+                    direction: Direction::Left,
+                    cells: vec![
+                        0, 3, 2, 5, 2, 3, 1, 0, // 0   1  2  3  4  5 6 7
+                        1, 2, 3, 1, 3, 1, 2, 1, // 15 14 13 12 11 10 9 8
+                    ],
+                },
+                OnePlayersField {
+                    direction: Direction::Right,
+                    cells: vec![
+                        0, 0, 0, 0, 1, 0, 0, 0, // 15 14 13 12 11 10 9 8
+                        0, 0, 0, 0, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
+                    ],
+                },
+            ],
+        };
+        assert!(matches!(
+            gamestate.make_move(12),
+            Err(GameError::RepeatingPosition)
+        ));
+        let legal_moves = gamestate.legal_moves_wasm();
+        assert!(!legal_moves.contains(&0) && !legal_moves.contains(&7)); // empty cells
+        assert!(!legal_moves.contains(&12)); // repeating position
+        assert!(!gamestate.is_legal_move(12));
+        assert!(!gamestate.is_legal_move(16));
+        assert_eq!(legal_moves.len(), 13);
+        assert_eq!(gamestate.occupied_cells().count(), 14);
+        let mut rng = Rng::new(7);
+        for _ in 0..100 {
+            let (position, _) = random_move(&gamestate, &mut rng).unwrap();
+            assert!(legal_moves.contains(&position));
+        }
+    }
 
-    #[wasm_bindgen_test]
-    fn wasm_test_playout() {
-        let state = GameState::default();
-        let ai = AI::new();
-        let res = ai.evaluate_state_for_next_move(&state, 1i32, 0);
+    #[test]
+    fn test_no_legal_moves_when_game_over() {
+        let gamestate = GameState {
+            game_over: true,
+            move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
+            curr_player: 0,
+            rules: RuleSet::default(),
+            fields_of_players: [
+                OnePlayersField {
+                    direction: Direction::Left,
+                    cells: vec![
+                        0, 0, 0, 1, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
+                        0, 0, 0, 0, 0, 0, 0, 0, // 15 14 13 12 11 10 9 8
+                    ],
+                },
+                OnePlayersField {
+                    direction: Direction::Right,
+                    cells: vec![0; 16],
+                },
+            ],
+        };
+        assert_eq!(gamestate.legal_moves().count(), 0);
+        assert!(!gamestate.is_legal_move(3));
+    }
 
-        assert!(res.is_ok());
+    #[test]
+    fn test_rule_set_with_different_lengths_of_field() {
+        assert!(RuleSet::new(3).is_err());
+        assert!(RuleSet::new(11).is_err());
+        assert_eq!(RuleSet::default().length_of_field(), 8);
+
+        for length_of_field in 4..=10 {
+            let rules = RuleSet::new(length_of_field).ok().unwrap();
+            let game = GameState::new(&rules);
+            assert_eq!(game.rules(), rules);
+            assert_eq!(game.fields_of_players[0].cells.len(), 2 * length_of_field);
+            assert_eq!(game.legal_moves().count(), 2 * length_of_field);
+            assert!(game.render().contains("Player 2"));
+
+            let mut ai = AI::new();
+            ai.set_max_depth(4);
+            for algorithm in [
+                Algorithm::FlatMonteCarlo,
+                Algorithm::MonteCarloTreeSearch,
+                Algorithm::AlphaBeta,
+            ]
+            .iter()
+            {
+                ai.set_algorithm(*algorithm);
+                let evaluation = ai
+                    .evaluate_state_with_budget(&game, &SearchBudget::playouts(1))
+                    .ok()
+                    .unwrap();
+                assert_eq!(evaluation.visits().len(), 2 * length_of_field);
+                assert!(game.is_legal_move(evaluation.recommended_move().unwrap()));
+            }
+        }
     }
 
     #[test]
-    fn test_game_playout_distribution() {
+    fn test_capture_on_small_field() {
         let gamestate = GameState {
             game_over: false,
             move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
             curr_player: 0,
+            rules: RuleSet::new(4).ok().unwrap(),
             fields_of_players: [
                 OnePlayersField {
-                    // Player 1, moves to the right
                     direction: Direction::Right,
-                    cells: [
-                        0, 0, 0, 1, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
-                        0, 0, 0, 0, 0, 0, 0, 0, // 15 14 13 12 11 10 9 8
+                    cells: vec![
+                        0, 1, 0, 0, // 0 1 2 3
+                        0, 0, 0, 1, // 7 6 5 4
                     ],
                 },
                 OnePlayersField {
-                    // Player 2, moves to the left
                     direction: Direction::Left,
-                    cells: [
-                        0, 0, 0, 0, 1, 0, 0, 0, // 15 14 13 12 11 10 9 8
-                        0, 0, 0, 0, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
+                    cells: vec![
+                        0, 0, 3, 0, // 7 6 5 4
+                        0, 2, 0, 0, // 0 1 2 3
                     ],
                 },
             ],
         };
 
-        let res = game_playout(gamestate, Some(1), None);
-        assert!(res.is_ok());
-        let distr = res.unwrap();
-        assert!(distr[3].0 > 0, "{:?}", distr);
+        let new_state = gamestate.make_move(1).ok().unwrap();
+        assert_eq!(new_state.move_statistic.last_move_position, 2);
+        // cell 2 and the cell behind it (8 - 2 - 1 = 5) are captured
+        assert_eq!(new_state.move_statistic.stones_of_opponent_removed, 5);
+        assert!(new_state.game_over());
+    }
+
+    #[test]
+    fn test_starting_layouts() {
+        let rules = RuleSet::default();
+
+        assert_eq!(
+            StartingLayout::from_name("uniform-3").ok(),
+            Some(StartingLayout::Uniform(3))
+        );
+        assert_eq!(
+            StartingLayout::from_name("front-4").ok(),
+            Some(StartingLayout::FrontRow(4))
+        );
+        assert_eq!(
+            StartingLayout::from_name("back-1").ok(),
+            Some(StartingLayout::BackRow(1))
+        );
+        assert!(StartingLayout::from_name("front").is_err());
+        assert!(StartingLayout::from_name("middle-2").is_err());
+        assert!(StartingLayout::from_name("uniform-x").is_err());
+
+        let game = GameState::with_layout(&rules, &StartingLayout::FrontRow(4))
+            .ok()
+            .unwrap();
+        assert_eq!(
+            game.fields_of_players[0].cells,
+            vec![4, 4, 4, 4, 4, 4, 4, 4, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            game.fields_of_players[1].cells,
+            game.fields_of_players[0].cells
+        );
+        assert_eq!(game.stones_of_player(0), 32);
+        assert_eq!(game.legal_moves().count(), 8);
+
+        let game = GameState::from_layout(&rules, "back-2").ok().unwrap();
+        assert_eq!(game.stones_of_player(1), 16);
+        assert_eq!(game.fields_of_players[1].cells[8..], [2; 8]);
+
+        // No stones or too many stones
+        assert!(GameState::with_layout(&rules, &StartingLayout::Uniform(0)).is_err());
+        assert!(GameState::with_layout(&rules, &StartingLayout::Uniform(16)).is_err());
+        assert!(GameState::with_layout(&rules, &StartingLayout::Uniform(15)).is_ok());
+    }
+
+    #[test]
+    fn test_arranged_starting_layout_is_validated() {
+        let rules = RuleSet::new(4).ok().unwrap();
+        let arranged = |cells0: Vec<u8>, cells1: Vec<u8>, stones_per_player: u32| {
+            GameState::with_layout(
+                &rules,
+                &StartingLayout::Arranged {
+                    cells: [cells0, cells1],
+                    stones_per_player,
+                },
+            )
+        };
+
+        let game = arranged(
+            vec![4, 0, 0, 4, 2, 2, 2, 2],
+            vec![0, 0, 8, 0, 0, 8, 0, 0],
+            16,
+        );
+        let game = game.ok().unwrap();
+        assert_eq!(
+            game.fields_of_players[0].cells,
+            vec![4, 0, 0, 4, 2, 2, 2, 2]
+        );
+        assert_eq!(
+            game.fields_of_players[1].cells,
+            vec![0, 0, 8, 0, 0, 8, 0, 0]
+        );
+        assert_eq!(game.curr_player, 0);
+        assert!(!game.game_over());
+
+        // Wrong total
+        assert!(arranged(
+            vec![4, 0, 0, 4, 2, 2, 2, 2],
+            vec![0, 0, 8, 0, 0, 8, 0, 0],
+            12
+        )
+        .is_err());
+        // Different totals
+        assert!(arranged(
+            vec![4, 0, 0, 4, 2, 2, 2, 2],
+            vec![0, 0, 8, 0, 0, 7, 0, 0],
+            16
+        )
+        .is_err());
+        // Wrong number of cells
+        assert!(arranged(vec![8, 8], vec![0, 0, 8, 0, 0, 8, 0, 0], 16).is_err());
+    }
+
+    #[test]
+    fn test_session_undo_redo_and_branching() {
+        let mut session = GameSession::default();
+        assert!(!session.can_undo() && !session.can_redo());
+        assert!(!session.undo());
+
+        let mut positions = vec![];
+        for _ in 0..3 {
+            let position = session.current_state().legal_moves_wasm()[0];
+            session.make_move(position).ok().unwrap();
+            positions.push(position);
+        }
+        assert!(session.make_move(16).is_err());
+        assert_eq!(session.current_ply(), 3);
+        assert_eq!(session.move_positions(), positions);
+        let state_after_3_plies = session.current_state();
+
+        assert!(session.undo());
+        assert!(session.undo());
+        assert_eq!(session.current_ply(), 1);
+        assert_eq!(session.current_state().curr_player, 1);
+        assert!(session.can_redo());
+        assert_eq!(session.number_of_plies(), 3);
+
+        assert!(session.redo());
+        assert!(session.redo());
+        assert!(!session.redo());
+        assert_eq!(
+            session.current_state().fields_of_players[1].cells,
+            state_after_3_plies.fields_of_players[1].cells
+        );
+
+        assert!(session.jump_to_ply(0));
+        assert!(!session.jump_to_ply(4));
+        assert_eq!(session.current_state().fields_of_players[0].cells, [2; 16]);
+
+        // New move after undo starts a new branch
+        assert!(session.jump_to_ply(1));
+        let position = *session.current_state().legal_moves_wasm().last().unwrap();
+        assert_ne!(position, positions[1]);
+        session.make_move(position).ok().unwrap();
+        assert_eq!(session.number_of_plies(), 2);
+        assert!(!session.can_redo());
+        assert_eq!(session.move_positions(), vec![positions[0], position]);
+        let played_move = session.move_at(2).unwrap();
+        assert_eq!(played_move.player, 1);
+        assert_eq!(played_move.position, position);
+        assert_eq!(
+            played_move.statistic.last_move_position,
+            session.current_state().move_statistic.last_move_position
+        );
+        assert!(session.move_at(0).is_none() && session.move_at(3).is_none());
+        assert_eq!(session.states().len(), 3);
+
+        // Moves in row and column notation are recorded by their position
+        let mut session = GameSession::default();
+        assert_eq!(
+            session.make_move_via_row_col(2, 0).err(),
+            Some(GameError::PositionOutsideField)
+        );
+        assert_eq!(session.number_of_plies(), 0);
+        session.make_move_via_row_col(1, 3).ok().unwrap();
+        assert_eq!(session.move_positions(), vec![3]);
+        assert_eq!(
+            session.current_state().hash(),
+            GameState::default().make_move(3).unwrap().hash()
+        );
+    }
+
+    #[test]
+    fn test_notation_round_trip() {
+        let game = GameState::default();
+        let notation = game.to_notation();
+        assert_eq!(notation, "2222222222222222/2222222222222222 0 L8");
+        let parsed = GameState::from_notation(&notation).unwrap();
+        assert_eq!(
+            parsed.fields_of_players[0].cells,
+            game.fields_of_players[0].cells
+        );
+        assert_eq!(
+            parsed.fields_of_players[1].cells,
+            game.fields_of_players[1].cells
+        );
+        assert_eq!(parsed.curr_player, 0);
+        assert_eq!(parsed.rules(), game.rules());
+
+        let game = GameState::from_notation("0a0(40)0000/(36)z000000 1").unwrap();
+        assert_eq!(game.rules().length_of_field(), 4);
+        assert_eq!(
+            game.fields_of_players[0].cells,
+            vec![0, 10, 0, 40, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            game.fields_of_players[1].cells,
+            vec![36, 35, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(game.curr_player, 1);
+        assert!(!game.game_over);
+        assert_eq!(game.to_notation(), "0a0(40)0000/(36)z000000 1 L4");
+
+        let game = GameState::from_notation("00000000/11000000 0 L4").unwrap();
+        assert!(game.game_over);
+    }
+
+    #[test]
+    fn test_notation_errors() {
+        use crate::notation::NotationError;
+        let err = |notation: &str| GameState::from_notation(notation).err().unwrap();
+
+        assert_eq!(
+            err("22222222/22222222"),
+            NotationError::WrongNumberOfParts(1)
+        );
+        assert_eq!(
+            err("22222222/22222222 0 L4 1 x"),
+            NotationError::WrongNumberOfParts(5)
+        );
+        assert_eq!(
+            err("22222222/22222222 0 L4 x"),
+            NotationError::InvalidPliesWithoutCapture("x".to_string())
+        );
+        assert_eq!(
+            err("22222222/22222222 0 L4 +1"),
+            NotationError::InvalidPliesWithoutCapture("+1".to_string())
+        );
+        assert_eq!(err("2222222222222222 0"), NotationError::MissingSeparator);
+        assert_eq!(err("22222222/2222/2222 0"), NotationError::MissingSeparator);
+        assert_eq!(
+            err("2222222-/22222222 0"),
+            NotationError::InvalidCell {
+                player: 0,
+                found: '-'
+            }
+        );
+        assert_eq!(
+            err("22222222/2222222(4 0"),
+            NotationError::InvalidCellNumber {
+                player: 1,
+                found: "4".to_string()
+            }
+        );
+        assert_eq!(
+            err("22222222/222222(x)2 0"),
+            NotationError::InvalidCellNumber {
+                player: 1,
+                found: "x".to_string()
+            }
+        );
+        assert_eq!(
+            err("22222222/222222(+2)2 0"),
+            NotationError::InvalidCellNumber {
+                player: 1,
+                found: "+2".to_string()
+            }
+        );
+        assert_eq!(
+            err("22222222/2222222 0 L4"),
+            NotationError::WrongNumberOfCells {
+                player: 1,
+                found: 7
+            }
+        );
+        assert_eq!(
+            err("222/222 0"),
+            NotationError::WrongNumberOfCells {
+                player: 0,
+                found: 3
+            }
+        );
+        assert_eq!(
+            err("22222222/(200)(100)000000 0"),
+            NotationError::TooManyStones { player: 1 }
+        );
+        assert_eq!(
+            err("(256)0000000/22222222 0"),
+            NotationError::TooManyStones { player: 0 }
+        );
+        assert_eq!(
+            err("(4294967295)(2)00000000000000/2222222222222222 0 L8"),
+            NotationError::TooManyStones { player: 0 }
+        );
+        assert_eq!(
+            err("22222222/22222222 2"),
+            NotationError::InvalidSideToMove("2".to_string())
+        );
+        assert_eq!(
+            err("22222222/22222222 0 L11"),
+            NotationError::InvalidRuleSet("L11".to_string())
+        );
+        assert_eq!(
+            err("22222222/22222222 0 X4"),
+            NotationError::InvalidRuleSet("X4".to_string())
+        );
+    }
+
+    #[test]
+    fn test_row_col_positions_are_relative_to_player() {
+        let game = GameState::default();
+        // Positions of row 0 (the back row) and row 1 (the front row) for the columns from the
+        // left. Before the move notation, row 0 was the front row of both players: positions
+        // 7..0 for player 0 and 0..8 for player 1.
+        let rows = [
+            [[15, 14, 13, 12, 11, 10, 9, 8], [0, 1, 2, 3, 4, 5, 6, 7]],
+            [[8, 9, 10, 11, 12, 13, 14, 15], [7, 6, 5, 4, 3, 2, 1, 0]],
+        ];
+        for (player, rows_of_player) in rows.iter().enumerate() {
+            for (row, positions) in rows_of_player.iter().enumerate() {
+                for (col, position) in positions.iter().enumerate() {
+                    assert_eq!(game.get_position_from_row_col(player, row, col), *position);
+                }
+            }
+        }
+        for player in 0..2 {
+            for position in 0..16 {
+                let (row, col) = game.get_row_col_from_position(player, position);
+                assert!(row < 2 && col < 8);
+                assert_eq!(game.get_position_from_row_col(player, row, col), position);
+            }
+        }
+        assert!(game.make_move_via_row_col(2, 0).is_err());
+        assert!(game.make_move_via_row_col(0, 8).is_err());
+        assert_eq!(
+            game.make_move_via_row_col(1, 2)
+                .ok()
+                .unwrap()
+                .fields_of_players[0]
+                .cells,
+            game.make_move(2).ok().unwrap().fields_of_players[0].cells
+        );
+    }
+
+    #[test]
+    fn test_move_notation() {
+        use crate::notation::NotationError;
+
+        let game = GameState::default();
+        assert_eq!(game.move_to_notation(0).unwrap(), "a2");
+        assert_eq!(game.move_to_notation(15).unwrap(), "a1");
+        let game = game.make_move(0).ok().unwrap();
+        assert_eq!(game.move_to_notation(0).unwrap(), "h2");
+        assert_eq!(game.move_to_notation(8).unwrap(), "a1");
+        assert_eq!(
+            game.move_to_notation(16),
+            Err(GameError::PositionOutsideField)
+        );
+        for (position, _) in game.legal_moves() {
+            let notation = game.move_to_notation(position).unwrap();
+            assert_eq!(game.move_from_notation(&notation), Ok(position));
+        }
+
+        let game = GameState::from_notation("0001000000000000/0010000000000100 0").unwrap();
+        assert_eq!(game.move_to_notation(3).unwrap(), "d2x2");
+        assert_eq!(game.move_from_notation("d2x2"), Ok(3));
+        assert_eq!(game.move_from_notation("d2"), Ok(3));
+        assert_eq!(
+            game.move_from_notation("d2x3"),
+            Err(NotationError::WrongCaptureSummary {
+                notation: "d2x3".to_string(),
+                captured: 2
+            })
+        );
+        assert_eq!(
+            game.move_from_notation("a2"),
+            Err(NotationError::IllegalMove {
+                notation: "a2".to_string(),
+                error: GameError::CellEmpty
+            })
+        );
+        assert_eq!(
+            game.move_from_notation("i2"),
+            Err(NotationError::IllegalMove {
+                notation: "i2".to_string(),
+                error: GameError::PositionOutsideField
+            })
+        );
+        assert_eq!(
+            game.move_from_notation("d3"),
+            Err(NotationError::IllegalMove {
+                notation: "d3".to_string(),
+                error: GameError::PositionOutsideField
+            })
+        );
+        for invalid in ["", "d", "2d", "D2", "d2x", "d2y2", "d+2", "d2x+0", "d2x 0"].iter() {
+            assert_eq!(
+                game.move_from_notation(invalid),
+                Err(NotationError::InvalidMove(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_draw_rules() {
+        use crate::history::PositionHistory;
+
+        let game =
+            GameState::from_notation("2222222222222222/2222222222222222 0 L8R2C50 7").unwrap();
+        assert_eq!(game.rules().repetitions_for_draw(), 2);
+        assert_eq!(game.rules().max_plies_without_capture(), 50);
+        assert_eq!(game.plies_without_capture(), 7);
+        assert_eq!(
+            game.to_notation(),
+            "2222222222222222/2222222222222222 0 L8R2C50 7"
+        );
+
+        // The counter is reset by every capture, the game is drawn when it reaches the limit
+        let mut rules = RuleSet::new(4).unwrap();
+        rules.set_max_plies_without_capture(3);
+        let mut game = GameState::new(&rules);
+        while game.outcome().is_none() {
+            let (_, new_state) = game.legal_moves().next().unwrap();
+            let expected = if new_state.move_statistic.stones_of_opponent_removed > 0 {
+                0
+            } else {
+                game.plies_without_capture() + 1
+            };
+            assert_eq!(new_state.plies_without_capture(), expected);
+            game = new_state;
+        }
+        if game.plies_without_capture() >= 3 {
+            assert_eq!(game.outcome(), Some(Outcome::Draw));
+            assert!(game.game_over() && game.legal_moves().next().is_none());
+        }
+
+        let game = GameState::from_notation("22222222/22222222 0 L4C5 5").unwrap();
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        let mut session = GameSession::new(&game);
+        assert_eq!(session.outcome(), Some(Outcome::Draw));
+        assert_eq!(session.make_move(0).err(), Some(GameError::NoMoveFound));
+
+        // Repetitions
+        let game = GameState::default();
+        let mut history = PositionHistory::new();
+        assert_eq!(history.push(&game), 1);
+        assert_eq!(history.push(&game.make_move(0).unwrap()), 1);
+        assert_eq!(history.push(&game), 2);
+        assert_eq!(history.outcome(&game), None);
+        assert_eq!(history.push(&game), 3);
+        assert_eq!(history.outcome(&game), Some(Outcome::Draw));
+        history.pop();
+        assert_eq!(history.occurrences(&game), 2);
+        assert_eq!(history.outcome(&game), None);
+        assert_eq!(history.len(), 3);
+
+        // A different position with the same hash is no repetition
+        let mut collision = game.make_move(1).unwrap();
+        collision.hash = game.hash();
+        assert_eq!(history.occurrences(&collision), 0);
+        assert_eq!(history.push(&collision), 1);
+        assert_eq!(history.occurrences(&game), 2);
+        history.pop();
+
+        let mut rules = RuleSet::default();
+        rules.set_repetitions_for_draw(0);
+        let game = GameState::new(&rules);
+        let history = PositionHistory::from_states(vec![&game, &game, &game, &game]);
+        assert_eq!(history.outcome(&game), None);
+    }
+
+    #[test]
+    fn test_zobrist_hash() {
+        use crate::zobrist::full_hash;
+        use std::collections::HashSet;
+
+        // The incrementally updated hash equals the hash of all cells
+        let mut game = GameState::default();
+        let mut hashes = HashSet::new();
+        while let Some((_, new_state)) = game.legal_moves().next() {
+            assert_eq!(new_state.hash(), full_hash(&new_state));
+            hashes.insert(new_state.hash());
+            game = new_state;
+        }
+        assert!(hashes.len() > 1);
+
+        // Equal positions have equal hashes, however they were created
+        let game = GameState::default().make_move(3).unwrap();
+        let parsed = GameState::from_notation(&game.to_notation()).unwrap();
+        assert_eq!(parsed.hash(), game.hash());
+        let json = serde_json::to_string(&game).unwrap();
+        assert_eq!(
+            serde_json::from_str::<GameState>(&json).unwrap().hash(),
+            game.hash()
+        );
+
+        // The player to move and the cells are distinguished
+        let hash = |notation: &str| GameState::from_notation(notation).unwrap().hash();
+        assert_ne!(hash("22222222/22222222 0"), hash("22222222/22222222 1"));
+        assert_ne!(hash("32222222/22222222 0"), hash("22222222/32222222 0"));
+        assert_ne!(hash("32222222/22222222 0"), hash("23222222/22222222 0"));
+        assert_ne!(hash("32222222/22222222 0"), hash("42222222/22222222 0"));
+        assert_eq!(
+            hash("22222222/22222222 0 L4C50 3"),
+            hash("22222222/22222222 0 L4")
+        );
+        // The same occupied cells on fields of different lengths
+        assert_ne!(
+            hash("10000000/10000000 0 L4"),
+            hash("1000000000000000/1000000000000000 0 L8")
+        );
+    }
+
+    #[test]
+    fn test_game_record_round_trip() {
+        use crate::record::{GameRecord, TAG_PLAYER0, TAG_RESULT, TAG_SETUP};
+
+        let mut session = GameSession::default();
+        while session.outcome().is_none() && session.current_ply() < 300 {
+            let legal_moves = session.current_state().legal_moves_wasm();
+            let position = legal_moves[session.current_ply() % legal_moves.len()];
+            session.make_move(position).ok().unwrap();
+        }
+        let text = session.to_record("Alice \"A\"", "Bob", "2021.03.14");
+        assert!(text.starts_with("[Player0 \"Alice \\\"A\\\"\"]\n[Player1 \"Bob\"]\n"));
+        assert!(text.contains("[RuleSet \"L8\"]") && !text.contains(TAG_SETUP));
+        assert!(text.lines().all(|line| line.len() <= 80));
+
+        let record = GameRecord::parse(&text).ok().unwrap();
+        assert_eq!(record.tag(TAG_PLAYER0), Some("Alice \"A\""));
+        assert_eq!(record.moves(), &session.move_positions()[..]);
+        assert_eq!(
+            record.final_state().fields_of_players[0].cells,
+            session.current_state().fields_of_players[0].cells
+        );
+        let expected_result = match session.outcome() {
+            Some(Outcome::Player0Wins) => "1-0",
+            Some(Outcome::Player1Wins) => "0-1",
+            Some(Outcome::Draw) => "1/2-1/2",
+            None => "*",
+        };
+        assert_eq!(record.tag(TAG_RESULT), Some(expected_result));
+        assert_eq!(record.to_string(), text);
+        assert_eq!(
+            record.to_session().move_positions(),
+            session.move_positions()
+        );
+
+        // Game from a setup where player 1 moves first
+        let setup = GameState::from_notation("00220000/02200000 1 L4").unwrap();
+        let mut record = GameRecord::new(&setup);
+        record.push_move(2).unwrap();
+        record.push_move(2).unwrap();
+        assert!(record.push_move(8).is_err());
+        let text = record.to_string();
+        assert!(
+            text.contains("[Setup \"00220000/02200000 1 L4\"]"),
+            "{}",
+            text
+        );
+        assert!(text.contains("\n\n1... b2 2. c2 *\n"), "{}", text);
+        let parsed = GameRecord::parse(&text).ok().unwrap();
+        assert_eq!(parsed.moves(), &[2, 2]);
+        assert_eq!(parsed.initial_state().to_notation(), setup.to_notation());
+    }
+
+    #[test]
+    fn test_game_record_is_validated() {
+        use crate::notation::NotationError;
+        use crate::record::{GameRecord, RecordError};
+        let err = |text: &str| GameRecord::parse(text).err().unwrap();
+
+        assert!(GameRecord::parse("1. a2 h2 2. b1 *").is_ok());
+        assert!(GameRecord::parse("").is_ok());
+        assert_eq!(
+            err("[Player0 Alice]"),
+            RecordError::InvalidTag("[Player0 Alice]".to_string())
+        );
+        assert_eq!(
+            err("[RuleSet \"L2\"]"),
+            RecordError::InvalidRuleSet("L2".to_string())
+        );
+        assert_eq!(
+            err("[RuleSet \"L8\"]\n[Setup \"22222222/22222222 0 L4\"]"),
+            RecordError::RuleSetDoesNotMatchSetup
+        );
+        assert_eq!(
+            err("[Setup \"22222222/22222222 0 L5\"]"),
+            RecordError::InvalidSetup(NotationError::WrongNumberOfCells {
+                player: 0,
+                found: 8
+            })
+        );
+        assert_eq!(
+            err("1. a2 h2 2. c2 *"),
+            RecordError::InvalidMove {
+                ply: 3,
+                error: NotationError::IllegalMove {
+                    notation: "c2".to_string(),
+                    error: GameError::CellEmpty
+                }
+            }
+        );
+        assert_eq!(
+            err("1. a2 h2 2. a3 *"),
+            RecordError::InvalidMove {
+                ply: 3,
+                error: NotationError::IllegalMove {
+                    notation: "a3".to_string(),
+                    error: GameError::PositionOutsideField
+                }
+            }
+        );
+        assert_eq!(
+            err("1. a2 2-0"),
+            RecordError::InvalidResult("2-0".to_string())
+        );
+        // A game may end early, e.g. by an agreed draw
+        assert!(GameRecord::parse("1. a2 1/2-1/2").is_ok());
+        assert_eq!(
+            err("[Result \"1-0\"]\n1. a2 0-1"),
+            RecordError::ResultMismatch {
+                tag: "1-0".to_string(),
+                moves: "0-1".to_string()
+            }
+        );
+        assert_eq!(
+            err("1. a2 * h2"),
+            RecordError::TextAfterResult("h2".to_string())
+        );
+        assert_eq!(
+            err("[Setup \"00010000/00100000 0 L4\"]\n1. d2x1 0-1"),
+            RecordError::WrongResult {
+                declared: "0-1".to_string(),
+                actual: "1-0".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let game = GameState::from_notation("0001000000000000/0010000000000100 0 L8").unwrap();
+        let game = game.make_move(3).ok().unwrap();
+        let json = serde_json::to_string(&game).unwrap();
+        let parsed: GameState = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.to_notation(), game.to_notation());
+        assert!(parsed.game_over);
+        assert_eq!(parsed.move_statistic.stones_of_opponent_removed, 2);
+        assert_eq!(parsed.move_statistic.last_move_position, 2);
+        assert_eq!(parsed.rules(), game.rules());
+
+        let game = GameState::from_notation("22222222/22222222 1 L4R5C40 7").unwrap();
+        let json = serde_json::to_string(&game).unwrap();
+        let parsed: GameState = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.to_notation(), "22222222/22222222 1 L4R5C40 7");
+        assert_eq!(parsed.rules(), game.rules());
+        assert_eq!(parsed.hash(), game.hash());
+
+        assert_eq!(
+            serde_json::to_string(&GameError::CellEmpty).unwrap(),
+            "\"CellEmpty\""
+        );
+        let error: GameError = serde_json::from_str("\"RepeatingPosition\"").unwrap();
+        assert_eq!(error, GameError::RepeatingPosition);
+
+        let evaluation = Evaluation::from_wins_losses(1, &[(1, 2), (0, 0), (3, 1)], None);
+        let json = serde_json::to_string(&evaluation).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Evaluation>(&json).unwrap(),
+            evaluation
+        );
+    }
+
+    #[test]
+    fn test_deserialized_game_state_is_validated() {
+        let json = serde_json::to_string(&GameState::new(&RuleSet::new(4).unwrap())).unwrap();
+        assert!(serde_json::from_str::<GameState>(&json).is_ok());
+
+        let invalid = |from: &str, to: &str| {
+            assert!(json.contains(from), "{}", json);
+            serde_json::from_str::<GameState>(&json.replace(from, to)).is_err()
+        };
+        assert!(invalid("\"length_of_field\":4", "\"length_of_field\":3"));
+        assert!(invalid("\"length_of_field\":4", "\"length_of_field\":5"));
+        assert!(invalid("\"curr_player\":0", "\"curr_player\":2"));
+        assert!(invalid("\"Right\"", "\"Left\""));
+        assert!(invalid("[2,2,2,2,2,2,2,2]", "[2,2,2,2,2,2,2]"));
+        assert!(invalid("[2,2,2,2,2,2,2,2]", "[200,2,2,2,2,2,2,200]"));
+    }
+
+    #[test]
+    fn test_deserialized_evaluation_is_validated() {
+        use crate::ai::difficulty::move_values;
+
+        let evaluation = Evaluation::from_wins_losses(1, &[(1, 2), (0, 0), (3, 1)], None);
+        let json = serde_json::to_string(&evaluation).unwrap();
+        let invalid = |from: &str, to: &str| {
+            assert!(json.contains(from), "{}", json);
+            serde_json::from_str::<Evaluation>(&json.replace(from, to)).is_err()
+        };
+        assert!(invalid("\"player\":1", "\"player\":2"));
+        assert!(invalid("\"wins\":[2,0,1]", "\"wins\":[2,0]"));
+        assert!(invalid("\"losses\":[1,0,3]", "\"losses\":[1,0,3,0]"));
+        assert!(invalid("\"draws\":[0,0,0]", "\"draws\":[0]"));
+        assert!(invalid("\"recommended_move\":0", "\"recommended_move\":3"));
+
+        // Evaluations of older versions have no draws and scores
+        let older = json
+            .replace(",\"draws\":[0,0,0]", "")
+            .replace(",\"scores\":[1,0,-2]", "");
+        let parsed = serde_json::from_str::<Evaluation>(&older).unwrap();
+        assert_eq!(parsed.wins(), vec![2, 0, 1]);
+        assert_eq!(move_values(&parsed).len(), 2);
+    }
+
+    #[test]
+    fn test_move_trace() {
+        let game = GameState::from_notation("90000000/10000000 0 L4").unwrap();
+        let (new_state, trace) = game.make_move_traced(0).ok().unwrap();
+        assert_eq!(trace.player(), 0);
+        assert_eq!(trace.start_position(), 0);
+        assert_eq!(
+            trace.drops(),
+            vec![7, 6, 5, 4, 3, 2, 1, 0, 7, 6, 5, 4, 3, 2, 1, 0, 7]
+        );
+        assert_eq!(trace.pickups(), vec![7, 5, 3, 1]);
+        assert_eq!(trace.last_position(), 7);
+        assert_eq!(trace.laps(), 2);
+        assert!(trace.captured_cells().is_empty());
+        assert_eq!(
+            new_state.to_notation(),
+            game.make_move(0).ok().unwrap().to_notation()
+        );
+
+        let game = GameState::from_notation("0001000000000000/0010000000000100 0").unwrap();
+        let (_, trace) = game.make_move_traced(3).ok().unwrap();
+        assert_eq!(trace.drops(), vec![2]);
+        assert!(trace.pickups().is_empty());
+        assert_eq!(trace.captured_cells(), vec![2, 13]);
+        assert_eq!(trace.laps(), 0);
+
+        let json = serde_json::to_string(&trace).unwrap();
+        let without_cells: crate::trace::MoveTrace =
+            serde_json::from_str(&json.replace("\"number_of_cells\":16", "\"number_of_cells\":0"))
+                .unwrap();
+        assert_eq!(without_cells.laps(), 0);
+
+        assert_eq!(game.make_move_traced(0).err(), Some(GameError::CellEmpty));
+
+        // Replaying the trace stone by stone gives the new state
+        let mut game = GameState::default();
+        for _ in 0..20 {
+            let position = match game.legal_moves().next() {
+                Some((position, _)) => position,
+                None => break,
+            };
+            let player = game.curr_player as usize;
+            let (new_state, trace) = game.make_move_traced(position).ok().unwrap();
+
+            let mut cells = game.fields_of_players[player].cells.clone();
+            let mut stones_in_the_hand = cells[position];
+            cells[position] = 0;
+            let mut pickups = vec![];
+            for (index, drop) in trace.drops().iter().enumerate() {
+                cells[*drop] += 1;
+                stones_in_the_hand -= 1;
+                if stones_in_the_hand == 0 && index + 1 < trace.drops().len() {
+                    stones_in_the_hand = cells[*drop];
+                    cells[*drop] = 0;
+                    pickups.push(*drop);
+                }
+            }
+            assert_eq!(cells, new_state.fields_of_players[player].cells);
+            assert_eq!(pickups, trace.pickups());
+
+            let mut opponent_cells = game.fields_of_players[1 - player].cells.clone();
+            for captured in trace.captured_cells() {
+                opponent_cells[captured] = 0;
+            }
+            assert_eq!(
+                opponent_cells,
+                new_state.fields_of_players[1 - player].cells
+            );
+            game = new_state;
+        }
+    }
+
+    use self::test::Bencher;
+    use crate::ai::budget::SearchBudget;
+    use crate::ai::evaluation::Evaluation;
+    use crate::ai::mcts::{tree_search, MonteCarloTreeSearch, DEFAULT_EXPLORATION_CONSTANT};
+    use crate::ai::rng::Rng;
+    use crate::ai::{game_playout, random_move, Algorithm, AI};
+
+    #[wasm_bindgen_test]
+    fn wasm_test_playout() {
+        let state = GameState::default();
+        let ai = AI::new();
+        let res = ai.evaluate_state_for_next_move(&state, 1i32, 0);
+
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_playouts_count_draws() {
+        use crate::ai::evaluation::PlayoutCounts;
+        use crate::ai::random_playout;
+
+        // Every move leads to a drawn position
+        let game = GameState::from_notation("22222222/22222222 0 L4C1").unwrap();
+        assert_eq!(
+            random_playout(game.make_move(0).unwrap(), &mut Rng::new(1)),
+            Ok(Outcome::Draw)
+        );
+        let counts = game_playout(game.clone(), &SearchBudget::playouts(2)).unwrap();
+        assert_eq!(
+            counts[0],
+            PlayoutCounts {
+                wins: [0, 0],
+                draws: 2
+            }
+        );
+        let evaluation = tree_search(
+            game,
+            DEFAULT_EXPLORATION_CONSTANT,
+            &SearchBudget::playouts(10),
+        )
+        .unwrap();
+        assert_eq!(evaluation.draws().iter().sum::<i32>(), 10);
+        assert_eq!(
+            evaluation.wins().iter().sum::<i32>() + evaluation.losses().iter().sum::<i32>(),
+            0
+        );
+
+        let evaluation = Evaluation::from_playout_counts(
+            1,
+            &[
+                PlayoutCounts {
+                    wins: [1, 2],
+                    draws: 3,
+                },
+                PlayoutCounts::default(),
+            ],
+            None,
+        );
+        assert_eq!(evaluation.wins(), vec![2, 0]);
+        assert_eq!(evaluation.losses(), vec![1, 0]);
+        assert_eq!(evaluation.draws(), vec![3, 0]);
+        assert_eq!(evaluation.visits(), vec![6, 0]);
+    }
+
+    #[test]
+    fn test_seeded_rng() {
+        use crate::ai::mcts::tree_search_with_policy;
+        use crate::ai::playout::UniformRandom;
+        use std::rc::Rc;
+
+        // Same numbers on every platform
+        let mut rng = Rng::new(42);
+        let numbers: Vec<u64> = (0..3).map(|_| rng.next_u64()).collect();
+        assert_eq!(
+            numbers,
+            vec![
+                0x1578_0b2e_0c2e_c716,
+                0x6104_d986_6d11_3a7e,
+                0xae17_5332_39e4_99a1
+            ]
+        );
+        assert_eq!(
+            Rng::new(42).gen_range(3, 10),
+            3 + ((0x1578_0b2e_0c2e_c716_u128 * 7) >> 64) as usize
+        );
+        for _ in 0..1000 {
+            let number = rng.gen_range(5, 8);
+            assert!((5..8).contains(&number));
+            let fraction = rng.gen_fraction();
+            assert!((0.0..1.0).contains(&fraction));
+        }
+        assert_eq!(rng.gen_range(4, 4), 4);
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+
+        // Searches with the same seed give the same results
+        let state = GameState::default();
+        let search = |seed: u64| {
+            tree_search_with_policy(
+                state.clone(),
+                DEFAULT_EXPLORATION_CONSTANT,
+                Rc::new(UniformRandom),
+                &mut Rng::new(seed),
+                &SearchBudget::playouts(100),
+            )
+            .unwrap()
+        };
+        assert_eq!(search(5), search(5));
+
+        for algorithm in [Algorithm::FlatMonteCarlo, Algorithm::MonteCarloTreeSearch].iter() {
+            let evaluations: Vec<Evaluation> = (0..2)
+                .map(|_| {
+                    let mut ai = AI::new();
+                    ai.set_algorithm(*algorithm);
+                    ai.set_seed(123);
+                    ai.evaluate_state_with_budget(&state, &SearchBudget::playouts(5))
+                        .ok()
+                        .unwrap()
+                })
+                .collect();
+            assert_eq!(evaluations[0], evaluations[1]);
+        }
+    }
+
+    #[test]
+    fn test_resumable_search() {
+        let state = GameState::default();
+        let ai_with = |algorithm: Algorithm| {
+            let mut ai = AI::new();
+            ai.set_algorithm(algorithm);
+            ai.set_max_depth(3);
+            ai.set_seed(7);
+            ai
+        };
+
+        // Steps accumulate and give the same result as a single search
+        for algorithm in [Algorithm::FlatMonteCarlo, Algorithm::MonteCarloTreeSearch].iter() {
+            let ai = ai_with(*algorithm);
+            let mut search = ai.start_search(&state).ok().unwrap();
+            assert_eq!(search.evaluation().visits().iter().sum::<i32>(), 0);
+            search.step(2);
+            let visits_after_first_step: i32 = search.evaluation().visits().iter().sum();
+            assert!(visits_after_first_step > 0);
+            search.step(3);
+            assert!(search.evaluation().visits().iter().sum::<i32>() > visits_after_first_step);
+            assert!(!search.is_finished());
+            let stepped = search.finish();
+
+            let ai = ai_with(*algorithm);
+            let single = ai
+                .evaluate_state_with_budget(&state, &SearchBudget::playouts(5))
+                .ok()
+                .unwrap();
+            assert_eq!(stepped, single);
+            assert!(state.is_legal_move(stepped.recommended_move().unwrap()));
+        }
+
+        // Every step of the alpha-beta search searches one ply deeper
+        let ai = ai_with(Algorithm::AlphaBeta);
+        let mut search = ai.start_search(&state).ok().unwrap();
+        assert_eq!(search.best_move(), None);
+        for _ in 0..3 {
+            assert!(!search.is_finished());
+            search.step(1);
+            assert!(search.best_move().is_some());
+        }
+        assert!(search.is_finished());
+        search.step(1);
+        let stepped = search.finish();
+        let single = ai_with(Algorithm::AlphaBeta)
+            .evaluate_state_with_budget(&state, &SearchBudget::default())
+            .ok()
+            .unwrap();
+        assert_eq!(stepped, single);
+
+        // Steps by time end in time
+        let ai = ai_with(Algorithm::MonteCarloTreeSearch);
+        let mut search = ai.start_search(&state).ok().unwrap();
+        let starttime = std::time::Instant::now();
+        search.step_for(20);
+        assert!(starttime.elapsed().as_millis() < 1000);
+        assert!(search.best_move().is_some());
+
+        // No search in finished games
+        let finished = GameState::from_notation("0000000000000000/0010000000000100 0").unwrap();
+        assert!(ai.search(&finished).is_err());
+    }
+
+    #[test]
+    fn test_difficulty_levels() {
+        use crate::ai::difficulty::{move_values, Difficulty, MoveChoice};
+
+        let evaluation = Evaluation::from_wins_losses(0, &[(9, 1), (5, 5), (1, 9), (0, 0)], None);
+        assert_eq!(
+            move_values(&evaluation),
+            vec![(0, 0.8), (1, 0.0), (2, -0.8)]
+        );
+        let scores =
+            Evaluation::from_scores(1, &[Some(400), None, Some(-400)], &[5, 0, 7], Some(0));
+        let values = move_values(&scores);
+        assert_eq!(values.len(), 2);
+        assert!(
+            (values[0].1 - 1f64.tanh()).abs() < 1e-9 && (values[1].1 + 1f64.tanh()).abs() < 1e-9
+        );
+
+        let mut rng = Rng::new(17);
+        let count_choices = |move_choice: MoveChoice, rng: &mut Rng| {
+            let mut counts = [0; 4];
+            for _ in 0..1000 {
+                counts[move_choice.choose(&evaluation, rng).unwrap()] += 1;
+            }
+            counts
+        };
+        assert_eq!(
+            count_choices(MoveChoice::default(), &mut rng),
+            [1000, 0, 0, 0]
+        );
+        let blunders = count_choices(
+            MoveChoice {
+                temperature: 0.0,
+                blunder_probability: 1.0,
+            },
+            &mut rng,
+        );
+        assert!(blunders[0] == 0 && blunders[1] > 0 && blunders[2] > 0 && blunders[3] == 0);
+        let noisy = count_choices(
+            MoveChoice {
+                temperature: 0.5,
+                blunder_probability: 0.0,
+            },
+            &mut rng,
+        );
+        assert!(noisy[0] > noisy[1] && noisy[1] > noisy[2] && noisy[2] > 0 && noisy[3] == 0);
+
+        // Weaker levels think less and make more mistakes
+        let settings: Vec<_> = Difficulty::ALL
+            .iter()
+            .map(|difficulty| difficulty.settings())
+            .collect();
+        for pair in settings.windows(2) {
+            assert!(pair[0].budget.max_playouts() <= pair[1].budget.max_playouts());
+            assert!(pair[0].move_choice.temperature >= pair[1].move_choice.temperature);
+            assert!(
+                pair[0].move_choice.blunder_probability >= pair[1].move_choice.blunder_probability
+            );
+        }
+        assert_eq!(settings[4].move_choice, MoveChoice::default());
+
+        let state = GameState::default();
+        let mut ai = AI::new();
+        ai.set_seed(5);
+        ai.set_difficulty(Difficulty::Beginner);
+        assert_eq!(ai.budget(), Difficulty::Beginner.settings().budget);
+        let position = ai.choose_move(&state).ok().unwrap();
+        assert!(state.is_legal_move(position));
+    }
+
+    #[test]
+    fn test_engine_protocol() {
+        use crate::engine::Engine;
+
+        let mut engine = Engine::new();
+        assert_eq!(engine.execute("boa").last().unwrap(), "boaok");
+        assert_eq!(engine.execute("isready"), vec!["readyok"]);
+        assert!(engine.execute("setoption name Seed value 3").is_empty());
+        assert!(engine.execute("position startpos moves c2 f2").is_empty());
+        let mut session = GameSession::default();
+        for notation in ["c2", "f2"] {
+            let position = session
+                .current_state_ref()
+                .move_from_notation(notation)
+                .unwrap();
+            session.make_move(position).unwrap();
+        }
+        assert!(engine.state().hash() == session.current_state_ref().hash());
+        let description = engine.execute("d");
+        assert_eq!(
+            description[0],
+            format!("position {}", engine.state().to_notation())
+        );
+        assert_eq!(description[2], "result *");
+
+        // Commands during the search are handled after it
+        assert!(engine.execute("go playouts 5").is_empty());
+        assert!(engine.is_searching());
+        assert_eq!(engine.execute("isready"), vec!["readyok"]);
+        assert!(engine.execute("moves a2").is_empty());
+        let mut lines = vec![];
+        while engine.is_searching() {
+            lines.extend(engine.think());
+        }
+        assert!(lines.iter().any(|line| line.starts_with("info ")));
+        let best_move = lines
+            .iter()
+            .find_map(|line| line.strip_prefix("bestmove "))
+            .unwrap();
+        // Without the option Difficulty, the best move of the search is played
+        let info = &lines[lines.len() - 2];
+        assert!(
+            info.contains(&format!(" bestmove {} ", best_move)),
+            "{}",
+            info
+        );
+        assert!(description[1]
+            .split_whitespace()
+            .any(|legal_move| legal_move == best_move));
+        session
+            .make_move(
+                session
+                    .current_state_ref()
+                    .move_from_notation("a2")
+                    .unwrap(),
+            )
+            .unwrap();
+        assert!(engine.state().hash() == session.current_state_ref().hash());
+
+        // A stopped search still answers with a legal move
+        engine.execute("go infinite");
+        let best_move = engine.execute("stop")[0]
+            .strip_prefix("bestmove ")
+            .unwrap()
+            .to_string();
+        assert!(engine.state().move_from_notation(&best_move).is_ok());
+
+        assert!(engine
+            .execute("setoption name Algorithm value AlphaBeta")
+            .is_empty());
+        assert!(engine
+            .execute("position notation 0001000000000000/0010000000000100 0")
+            .is_empty());
+        engine.execute("go movetime 50");
+        while engine.is_searching() {
+            lines = engine.think();
+        }
+        let best_move = lines.last().unwrap().strip_prefix("bestmove ").unwrap();
+        assert!(engine.state().move_from_notation(best_move).is_ok());
+
+        for invalid in [
+            "foo",
+            "position nowhere",
+            "moves z9",
+            "setoption name Seed value x",
+            "rules Q",
+        ] {
+            assert!(
+                engine.execute(invalid)[0].starts_with("error "),
+                "{}",
+                invalid
+            );
+        }
+
+        // stop and quit end the search at once, the commands before them wait for the end
+        assert!(engine.execute("go infinite").is_empty());
+        assert!(engine.execute("d").is_empty());
+        let answer = engine.execute("stop");
+        assert!(answer[0].starts_with("bestmove "), "{:?}", answer);
+        assert_eq!(
+            answer[1],
+            format!("position {}", engine.state().to_notation())
+        );
+        assert!(!engine.is_searching());
+        assert!(engine.execute("go infinite").is_empty());
+        assert!(engine.execute("d").is_empty());
+        assert!(!engine.has_quit());
+        let answer = engine.execute("quit");
+        assert_eq!(answer.len(), 1);
+        assert!(answer[0].starts_with("bestmove "), "{:?}", answer);
+        assert!(engine.has_quit() && !engine.is_searching());
+    }
+
+    #[test]
+    fn test_match_statistics() {
+        use crate::tournament::{
+            elo_from_score, play_match_game, score_from_elo, MatchScore, Sprt, SprtStatus,
+        };
+
+        let mut score = MatchScore::default();
+        assert_eq!(score.elo_difference(), None);
+        score.add(Outcome::Player0Wins, true);
+        score.add(Outcome::Player0Wins, false);
+        score.add(Outcome::Draw, false);
+        assert_eq!(
+            score,
+            MatchScore {
+                wins: 1,
+                draws: 1,
+                losses: 1
+            }
+        );
+        assert_eq!(score.elo_difference(), Some(0.0));
+        assert!((elo_from_score(score_from_elo(100.0)) - 100.0).abs() < 1e-9);
+        assert!((elo_from_score(0.75) - 190.85).abs() < 0.01);
+
+        // The interval shrinks with more games
+        let small = MatchScore {
+            wins: 15,
+            draws: 0,
+            losses: 5,
+        };
+        let large = MatchScore {
+            wins: 150,
+            draws: 0,
+            losses: 50,
+        };
+        assert_eq!(small.elo_difference(), large.elo_difference());
+        assert!(small.elo_error().unwrap() > 2.0 * large.elo_error().unwrap());
+
+        let sprt = Sprt::new(0.0, 20.0);
+        let (lower, upper) = sprt.bounds();
+        assert!((lower + 2.944).abs() < 0.001 && (upper - 2.944).abs() < 0.001);
+        assert_eq!(
+            sprt.log_likelihood_ratio(&MatchScore {
+                wins: 5,
+                draws: 0,
+                losses: 0
+            }),
+            0.0
+        );
+        assert_eq!(
+            sprt.status(&MatchScore {
+                wins: 11,
+                draws: 0,
+                losses: 9
+            }),
+            SprtStatus::Continue
+        );
+        assert_eq!(sprt.status(&large), SprtStatus::AcceptH1);
+        assert_eq!(
+            sprt.status(&MatchScore {
+                wins: 50,
+                draws: 0,
+                losses: 150
+            }),
+            SprtStatus::AcceptH0
+        );
+
+        // The colours alternate
+        let ais = [0, 1].map(|seed| {
+            let mut ai = AI::new();
+            ai.set_seed(seed);
+            ai.set_budget(&SearchBudget::playouts(2));
+            ai
+        });
+        for game in 0..2 {
+            let record =
+                play_match_game(&ais, ["first", "second"], game, &GameState::default()).unwrap();
+            assert!(record.outcome().is_some());
+            let player0 = if game == 0 { "first" } else { "second" };
+            assert_eq!(record.tag(crate::record::TAG_PLAYER0), Some(player0));
+        }
+    }
+
+    #[test]
+    fn test_tablebase() {
+        use crate::ai::tablebase::{Tablebase, TablebaseError, TablebaseResult};
+
+        let tablebase = Tablebase::generate(4, 2).unwrap();
+        let bytes = tablebase.to_bytes();
+        let tablebase = Tablebase::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            (
+                tablebase.length_of_field(),
+                tablebase.max_stones(),
+                tablebase.size()
+            ),
+            (4, 2, 2 * 44 * 44)
+        );
+        assert_eq!(
+            Tablebase::from_bytes(&bytes[..bytes.len() - 2]).err(),
+            Some(TablebaseError::WrongSize {
+                expected: bytes.len(),
+                found: bytes.len() - 2
+            })
+        );
+        assert_eq!(
+            Tablebase::from_bytes(b"PGN").err(),
+            Some(TablebaseError::NotATablebase)
+        );
+        assert_eq!(
+            Tablebase::generate(3, 2).err(),
+            Some(TablebaseError::InvalidLengthOfField(3))
+        );
+        // Too many positions: 2 * 20348^2
+        assert_eq!(
+            Tablebase::generate(8, 5).err(),
+            Some(TablebaseError::InvalidMaxStones(5))
+        );
+        let mut header = bytes[..7].to_vec();
+        header[5..7].copy_from_slice(&[8, 5]);
+        assert_eq!(
+            Tablebase::from_bytes(&header).err(),
+            Some(TablebaseError::InvalidMaxStones(5))
+        );
+
+        // All fields with 1 or 2 stones
+        let mut fields = vec![];
+        for first in 0..8 {
+            for second in first..9 {
+                let mut cells = [0; 8];
+                cells[first] += 1;
+                if second < 8 {
+                    cells[second] += 1;
+                }
+                fields.push(
+                    cells
+                        .iter()
+                        .map(|stones| stones.to_string())
+                        .collect::<String>(),
+                );
+            }
+        }
+        assert_eq!(fields.len(), 44);
+
+        // Every result follows from the results after the moves
+        let positions = fields
+            .iter()
+            .flat_map(|field0| fields.iter().map(move |field1| (field0, field1)));
+        for ((field0, field1), player) in positions.flat_map(|fields| [(fields, 0), (fields, 1)]) {
+            let state =
+                GameState::from_notation(&format!("{}/{} {} L4", field0, field1, player)).unwrap();
+            let mut wins_now = false;
+            let mut results = vec![];
+            for (_, next_state) in state.legal_moves() {
+                match next_state.outcome() {
+                    Some(_) => wins_now = true,
+                    None => results.push(tablebase.probe(&next_state).unwrap()),
+                }
+            }
+            let distances = |won: bool| {
+                results
+                    .iter()
+                    .filter_map(move |result| match (result, won) {
+                        (TablebaseResult::Loss(distance), true)
+                        | (TablebaseResult::Win(distance), false) => Some(distance + 1),
+                        _ => None,
+                    })
+            };
+            let fastest_win = distances(true).min();
+            let expected = if wins_now {
+                TablebaseResult::Win(1)
+            } else if let Some(distance) = fastest_win {
+                TablebaseResult::Win(distance)
+            } else if !results.is_empty() && distances(false).count() == results.len() {
+                TablebaseResult::Loss(distances(false).max().unwrap())
+            } else {
+                TablebaseResult::Draw
+            };
+            assert_eq!(
+                tablebase.probe(&state),
+                Some(expected),
+                "{}",
+                state.to_notation()
+            );
+        }
+
+        // The AI plays the fastest win
+        let state = GameState::from_notation("01000000/00000010 0 L4").unwrap();
+        let mut ai = AI::new();
+        ai.set_budget(&SearchBudget::playouts(1));
+        ai.set_tablebase(Some(std::rc::Rc::new(tablebase.clone())));
+        let position = ai.next_move(&state).unwrap();
+        assert_eq!(Some(position), tablebase.best_move(&state));
+        let result = tablebase.probe(&state).unwrap();
+        let next_state = state.make_move(position).unwrap();
+        match result {
+            TablebaseResult::Win(1) => assert!(next_state.outcome().is_some()),
+            TablebaseResult::Win(distance) => assert_eq!(
+                tablebase.probe(&next_state),
+                Some(TablebaseResult::Loss(distance - 1))
+            ),
+            _ => panic!("Expected a win, found {:?}.", result),
+        }
+        assert!(!tablebase.contains(&GameState::default()));
+
+        // The move from 0 is drawn by the limit of plies without capture, the one from 2 wins
+        let state = GameState::from_notation("10100000/01000000 0 L4C5 4").unwrap();
+        assert_eq!(state.make_move(0).unwrap().outcome(), Some(Outcome::Draw));
+        assert_eq!(tablebase.best_move(&state), Some(2));
+    }
+
+    #[test]
+    fn test_opening_book() {
+        use crate::ai::book::{BookError, BookSettings, OpeningBook, MAX_WEIGHT};
+
+        let state = GameState::default();
+        let mut ai = AI::new();
+        ai.set_seed(11);
+        let settings = BookSettings {
+            max_plies: 2,
+            max_moves: 2,
+            margin: 2.0,
+            budget: SearchBudget::playouts(2),
+        };
+        let book = OpeningBook::generate(&ai, &state, &settings).unwrap();
+        assert_eq!((book.number_of_positions(), book.size()), (3, 6));
+        let moves = book.moves_of(&state);
+        assert_eq!(moves.len(), 2);
+        assert!(moves.iter().any(|book_move| book_move.weight == MAX_WEIGHT));
+        for book_move in moves {
+            let next_state = state.make_move(book_move.position as usize).unwrap();
+            assert_eq!(book.moves_of(&next_state).len(), 2);
+            for next_move in book.moves_of(&next_state) {
+                assert!(!book.contains(&next_state.make_move(next_move.position as usize).unwrap()));
+            }
+        }
+
+        // The book does not depend on a search kept from another position
+        let mut fresh_ai = AI::new();
+        fresh_ai.set_seed(11);
+        fresh_ai.set_reuse_search(false);
+        assert_eq!(
+            OpeningBook::generate(&fresh_ai, &state, &settings)
+                .unwrap()
+                .moves(),
+            book.moves()
+        );
+
+        let bytes = book.to_bytes();
+        assert_eq!(
+            OpeningBook::from_bytes(&bytes).unwrap().moves(),
+            book.moves()
+        );
+        assert_eq!(
+            OpeningBook::from_bytes(&bytes[..bytes.len() - 1]).err(),
+            Some(BookError::WrongSize(bytes.len() - 1))
+        );
+        assert_eq!(
+            OpeningBook::from_bytes(b"BOAT").err(),
+            Some(BookError::NotABook)
+        );
+        // The weights of the alpha-beta search follow the exact scores of all moves
+        use crate::ai::alphabeta::AlphaBetaSearch;
+        let mut alpha_beta = AI::new();
+        alpha_beta.set_algorithm(Algorithm::AlphaBeta);
+        alpha_beta.set_max_depth(3);
+        let settings = BookSettings {
+            max_plies: 1,
+            max_moves: 16,
+            margin: 2.0,
+            budget: SearchBudget::default(),
+        };
+        let alpha_beta_book = OpeningBook::generate(&alpha_beta, &state, &settings).unwrap();
+        let mut weights_by_score: Vec<(i32, u16)> = alpha_beta_book
+            .moves_of(&state)
+            .iter()
+            .map(|book_move| {
+                let mut search =
+                    AlphaBetaSearch::new(state.make_move(book_move.position as usize).unwrap());
+                search.run(2, &SearchBudget::default());
+                (
+                    -search.root_scores().iter().flatten().max().unwrap(),
+                    book_move.weight,
+                )
+            })
+            .collect();
+        assert_eq!(weights_by_score.len(), state.legal_moves().count());
+        weights_by_score.sort();
+        assert!(weights_by_score
+            .windows(2)
+            .all(|pair| pair[0].0 == pair[1].0 || pair[0].1 < pair[1].1));
+
+        let mut unordered = bytes[..6].to_vec();
+        unordered.extend_from_slice(&bytes[17..28]);
+        unordered.extend_from_slice(&bytes[6..17]);
+        assert_eq!(
+            OpeningBook::from_bytes(&unordered).err(),
+            Some(BookError::UnorderedMoves(1))
+        );
+
+        // The moves are chosen by their weights
+        let mut book = OpeningBook::new(8).unwrap();
+        book.add(&state, 2, 1).unwrap();
+        book.add(&state, 5, 3).unwrap();
+        assert_eq!(
+            book.add(&state, 20, 1),
+            Err(GameError::PositionOutsideField)
+        );
+        assert!(!book.contains(&GameState::new(&RuleSet::new(6).unwrap())));
+        let mut rng = Rng::new(3);
+        let mut counts = [0; 16];
+        for _ in 0..1000 {
+            counts[book.choose(&state, &mut rng).unwrap()] += 1;
+        }
+        assert_eq!(counts[2] + counts[5], 1000);
+        assert!(counts[5] > 2 * counts[2] && counts[2] > 150);
+
+        ai.set_opening_book(Some(std::rc::Rc::new(book)));
+        ai.set_budget(&SearchBudget::playouts(1));
+        for _ in 0..10 {
+            assert!([2, 5].contains(&ai.next_move(&state).unwrap()));
+        }
+        assert_eq!(ai.book_move(&state.make_move(2).unwrap()), None);
+    }
+
+    #[test]
+    fn test_search_reuse() {
+        let state = GameState::default();
+        let mut ai = AI::new();
+        ai.set_seed(11);
+
+        // The tree search keeps the subtree of the move
+        let mut search = ai.search(&state).unwrap();
+        search.step(20);
+        let best_move = search.best_move().unwrap();
+        let visits_of_move = search.evaluation().visits()[best_move];
+        search.advance(best_move).unwrap();
+        let new_state = state.make_move(best_move).unwrap();
+        assert_eq!(search.state().hash(), new_state.hash());
+        let visits: i32 = search.evaluation().visits().iter().sum();
+        assert!(visits > 0 && visits < visits_of_move);
+        search.step(1);
+        assert!(new_state.is_legal_move(search.best_move().unwrap()));
+
+        // Illegal moves and unreachable positions leave the search unchanged
+        assert!(search.advance(new_state.rules.number_of_cells()).is_err());
+        let other_state = GameState::from_notation("0001000000000000/0010000000000100 0").unwrap();
+        assert!(!search.advance_to(&other_state));
+        assert_eq!(search.state().hash(), new_state.hash());
+        let (_, after_reply) = new_state.legal_moves().next().unwrap();
+        let (_, after_answer) = after_reply.legal_moves().next().unwrap();
+        // Same hash, but different draw rules
+        let mut other_rules = after_answer.clone();
+        other_rules.rules.set_max_plies_without_capture(50);
+        assert_eq!(other_rules.hash(), after_answer.hash());
+        assert!(!search.advance_to(&other_rules));
+        assert!(search.advance_to(&after_answer));
+        assert_eq!(search.state().hash(), after_answer.hash());
+        assert!(search.advance_to(&after_answer));
+
+        // The AI continues its last search after pondering during the turn of the opponent
+        let single_playouts = after_answer.legal_moves().count() as i32;
+        for reuse_search in [false, true].iter() {
+            let mut ai = AI::new();
+            ai.set_seed(3);
+            ai.set_reuse_search(*reuse_search);
+            ai.ponder(&after_reply, &SearchBudget::playouts(50))
+                .ok()
+                .unwrap();
+            let evaluation = ai
+                .evaluate_state_with_budget(&after_answer, &SearchBudget::playouts(1))
+                .ok()
+                .unwrap();
+            let visits: i32 = evaluation.visits().iter().sum();
+            assert_eq!(
+                visits > single_playouts,
+                *reuse_search,
+                "reuse_search: {}",
+                reuse_search
+            );
+        }
+
+        // The other algorithms continue in the new position as well
+        for algorithm in [Algorithm::FlatMonteCarlo, Algorithm::AlphaBeta].iter() {
+            ai.set_algorithm(*algorithm);
+            ai.set_max_depth(3);
+            let mut search = ai.search(&state).unwrap();
+            search.step(5);
+            search.advance(best_move).unwrap();
+            search.step(5);
+            let evaluation = search.finish();
+            assert!(new_state.is_legal_move(evaluation.recommended_move().unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_evaluation_is_relative_to_player() {
+        let wins_losses = [(3, 1), (0, 0), (1, 4)];
+
+        let evaluation = Evaluation::from_wins_losses(0, &wins_losses, None);
+        assert_eq!(evaluation.wins(), vec![3, 0, 1]);
+        assert_eq!(evaluation.losses(), vec![1, 0, 4]);
+        assert_eq!(evaluation.visits(), vec![4, 0, 5]);
+        assert_eq!(evaluation.win_differences(), vec![2, 0, -3]);
+        assert_eq!(evaluation.recommended_move(), Some(0));
+
+        let evaluation = Evaluation::from_wins_losses(1, &wins_losses, None);
+        assert_eq!(evaluation.player(), 1);
+        assert_eq!(evaluation.win_differences(), vec![-2, 0, 3]);
+        assert_eq!(evaluation.recommended_move(), Some(2));
+
+        let evaluation = Evaluation::from_wins_losses(1, &wins_losses, Some(1));
+        assert_eq!(evaluation.recommended_move(), Some(1));
+    }
+
+    #[test]
+    fn test_evaluate_state_for_next_move_recommends_possible_move() {
+        let state = GameState::default();
+        // Without any playouts and time, the searches still search once
+        for num_rounds in [2, 0].iter() {
+            for algorithm in Algorithm::ALL.iter() {
+                let mut ai = AI::new();
+                ai.set_algorithm(*algorithm);
+                let evaluation = ai
+                    .evaluate_state_for_next_move(&state, *num_rounds, 0)
+                    .ok()
+                    .unwrap();
+                assert_eq!(evaluation.player(), state.curr_player);
+                assert_eq!(evaluation.visits().len(), 16);
+                let recommended_move = evaluation.recommended_move().unwrap();
+                assert!(state.make_move(recommended_move).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_game_playout_distribution() {
+        let gamestate = GameState {
+            game_over: false,
+            move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
+            curr_player: 0,
+            rules: RuleSet::default(),
+            fields_of_players: [
+                OnePlayersField {
+                    // Player 1, moves to the right
+                    direction: Direction::Right,
+                    cells: vec![
+                        0, 0, 0, 1, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
+                        0, 0, 0, 0, 0, 0, 0, 0, // 15 14 13 12 11 10 9 8
+                    ],
+                },
+                OnePlayersField {
+                    // Player 2, moves to the left
+                    direction: Direction::Left,
+                    cells: vec![
+                        0, 0, 0, 0, 1, 0, 0, 0, // 15 14 13 12 11 10 9 8
+                        0, 0, 0, 0, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
+                    ],
+                },
+            ],
+        };
+
+        let res = game_playout(gamestate, &SearchBudget::playouts(1));
+        assert!(res.is_ok());
+        let distr = res.unwrap();
+        assert!(distr[3].wins[0] > 0, "{:?}", distr);
+    }
+
+    #[test]
+    fn test_tree_search_finds_winning_move() {
+        let gamestate = GameState {
+            game_over: false,
+            move_statistic: MoveStatistic::default(),
+            plies_without_capture: 0,
+            hash: 0,
+            curr_player: 0,
+            rules: RuleSet::default(),
+            fields_of_players: [
+                OnePlayersField {
+                    // Player 1, moves to the right
+                    direction: Direction::Right,
+                    cells: vec![
+                        0, 0, 0, 1, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
+                        0, 0, 1, 0, 0, 0, 0, 0, // 15 14 13 12 11 10 9 8
+                    ],
+                },
+                OnePlayersField {
+                    // Player 2, moves to the left
+                    direction: Direction::Left,
+                    cells: vec![
+                        0, 0, 0, 0, 1, 0, 0, 0, // 15 14 13 12 11 10 9 8
+                        0, 0, 0, 0, 0, 0, 0, 0, // 0   1  2  3  4  5 6 7
+                    ],
+                },
+            ],
+        };
+
+        let mut search = MonteCarloTreeSearch::new(gamestate.clone(), DEFAULT_EXPLORATION_CONSTANT);
+        assert_eq!(search.run(&SearchBudget::playouts(200)), 200);
+        assert_eq!(search.best_move(), Some(3));
+
+        let evaluation = tree_search(
+            gamestate,
+            DEFAULT_EXPLORATION_CONSTANT,
+            &SearchBudget::playouts(200),
+        )
+        .unwrap();
+        assert_eq!(evaluation.player(), 0);
+        assert_eq!(evaluation.recommended_move(), Some(3));
+        assert!(
+            evaluation.wins()[3] > 0 && evaluation.losses()[3] == 0,
+            "{:?}",
+            evaluation
+        );
+        assert_eq!(evaluation.visits().iter().sum::<i32>(), 200);
+    }
+
+    #[test]
+    fn test_alpha_beta_search() {
+        use crate::ai::alphabeta::{alpha_beta_search, AlphaBetaSearch, WIN_SCORE};
+        use crate::ai::evaluator::StoneDifference;
+        use std::rc::Rc;
+
+        // Moving from 3 captures the last stones of player 1
+        let game = GameState::from_notation("0001000000000000/0010000000000100 0").unwrap();
+        let mut search = AlphaBetaSearch::new(game.clone());
+        assert_eq!(search.run(4, &SearchBudget::default()), 4);
+        assert_eq!(search.best_move(), Some(3));
+        assert_eq!(search.root_scores()[3], Some(WIN_SCORE - 1));
+        assert_eq!(search.root_scores()[0], None);
+
+        // Deterministic results
+        let game = GameState::default();
+        let evaluate = |game: &GameState| {
+            alpha_beta_search(
+                game.clone(),
+                Rc::new(StoneDifference),
+                5,
+                &SearchBudget::default(),
+            )
+        };
+        let evaluation = evaluate(&game).unwrap();
+        assert_eq!(evaluate(&game).unwrap(), evaluation);
+        let recommended_move = evaluation.recommended_move().unwrap();
+        assert!(game.make_move(recommended_move).is_ok());
+        assert_eq!(
+            evaluation.scores().iter().max(),
+            evaluation.scores().get(recommended_move)
+        );
+        assert_eq!(evaluation.wins().iter().sum::<i32>(), 0);
+
+        // The scores of all moves are exact, not only the one of the best move
+        let mut search = AlphaBetaSearch::new(game.clone());
+        search.run(3, &SearchBudget::default());
+        for (position, child) in game.legal_moves() {
+            let mut child_search = AlphaBetaSearch::new(child);
+            child_search.run(2, &SearchBudget::default());
+            let child_score = child_search.root_scores().iter().flatten().max().unwrap();
+            assert_eq!(search.root_scores()[position], Some(-child_score));
+        }
+
+        // The time limit stops the iterative deepening, but the first iteration is completed
+        let mut search = AlphaBetaSearch::new(game);
+        let starttime = instant::Instant::now();
+        let depth = search.run(100, &SearchBudget::time(50));
+        assert!(starttime.elapsed().as_millis() < 1000);
+        assert!((1..100).contains(&depth));
+        assert!(search.best_move().is_some());
+
+        let game = GameState::from_notation("00000000/11000000 0 L4").unwrap();
+        assert_eq!(evaluate(&game).err(), Some(GameError::NoMoveFound));
+    }
+
+    #[test]
+    fn test_evaluators() {
+        use crate::ai::alphabeta::{SCORE_PER_UNIT, WIN_SCORE};
+        use crate::ai::evaluator::{
+            with_player_to_move, Evaluator, EvaluatorKind, Mobility, RowWeighted, StoneDifference,
+            Vulnerability, WeightedSum,
+        };
+        use crate::zobrist::full_hash;
+
+        // Player 0: 3 stones in the front row, 1 in the back row; player 1: 1 stone at 2
+        let game = GameState::from_notation("20010010/00100000 0 L4").unwrap();
+        assert_eq!(StoneDifference.evaluate(&game, 0), 3.0);
+        assert_eq!(StoneDifference.evaluate(&game, 1), -3.0);
+        let row_weighted = RowWeighted {
+            front_row_weight: 1.0,
+            back_row_weight: 2.0,
+        };
+        assert_eq!(row_weighted.evaluate(&game, 0), 5.0 - 1.0);
+        assert_eq!(Mobility.evaluate(&game, 0), 3.0 - 1.0);
+        assert_eq!(Mobility.evaluate(&game, 1), -2.0);
+
+        // The evaluators look at the moves of the player who is not to move with the right hash
+        for player in 0..2 {
+            let state = with_player_to_move(&game, player);
+            assert_eq!(state.hash(), full_hash(&state));
+            for (_, new_state) in state.legal_moves() {
+                assert_eq!(new_state.hash(), full_hash(&new_state));
+            }
+        }
+
+        // Moving from 3, player 0 captures the last stone of player 1
+        assert_eq!(
+            game.make_move(3)
+                .unwrap()
+                .move_statistic
+                .stones_of_opponent_removed,
+            1
+        );
+        assert_eq!(Vulnerability.evaluate(&game, 1), -1.0);
+        let combined = WeightedSum::new()
+            .with(1.0, StoneDifference)
+            .with(0.5, Vulnerability);
+        assert_eq!(combined.evaluate(&game, 1), -3.5);
+
+        for kind in [
+            EvaluatorKind::StoneDifference,
+            EvaluatorKind::RowWeighted,
+            EvaluatorKind::Mobility,
+            EvaluatorKind::Vulnerability,
+            EvaluatorKind::Balanced,
+        ]
+        .iter()
+        {
+            // The beginning of the game is symmetric
+            let game = GameState::default();
+            assert_eq!(
+                game.evaluate(*kind, 0),
+                game.evaluate(*kind, 1),
+                "{:?}",
+                kind
+            );
+        }
+
+        // The alpha-beta search uses the evaluator of the AI
+        let mut ai = AI::new();
+        ai.set_algorithm(Algorithm::AlphaBeta);
+        ai.set_max_depth(1);
+        ai.set_evaluator(row_weighted);
+        let evaluation = ai
+            .evaluate_state_with_budget(&game, &SearchBudget::default())
+            .ok()
+            .unwrap();
+        assert_eq!(evaluation.recommended_move(), Some(3));
+        assert_eq!(evaluation.scores()[3], WIN_SCORE - 1);
+        assert_eq!(evaluation.scores()[6], (4.0 * SCORE_PER_UNIT) as i32);
+    }
+
+    #[test]
+    fn test_playout_policies() {
+        use crate::ai::evaluator::StoneDifference;
+        use crate::ai::playout::{
+            GreedyCapture, PlayoutPolicy, PlayoutPolicyKind, Softmax, UniformRandom,
+        };
+        use crate::ai::playout_with_policy;
+
+        // Only the move from 3 captures stones
+        let game = GameState::from_notation("0001000000000000/0010000000000100 0").unwrap();
+        let rng = &mut Rng::new(3);
+        let greedy = GreedyCapture { epsilon: 0.0 };
+        let softmax = Softmax::new(StoneDifference, 0.1).unwrap();
+        for invalid in [0.0, -1.0, f32::NAN, f32::INFINITY].iter() {
+            assert!(Softmax::new(StoneDifference, *invalid).is_none());
+        }
+        for _ in 0..20 {
+            assert_eq!(greedy.choose_move(&game, rng).unwrap().0, 3);
+            assert_eq!(softmax.choose_move(&game, rng).unwrap().0, 3);
+            assert!(game.is_legal_move(UniformRandom.choose_move(&game, rng).unwrap().0));
+        }
+
+        // Without captures, every legal move may be chosen
+        let game = GameState::default();
+        let positions: std::collections::HashSet<usize> = (0..200)
+            .map(|_| greedy.choose_move(&game, rng).unwrap().0)
+            .collect();
+        assert!(positions.len() > 1);
+
+        let game = GameState::from_notation("00000000/11000000 0 L4").unwrap();
+        assert!(greedy.choose_move(&game, rng).is_none());
+        assert!(softmax.choose_move(&game, rng).is_none());
+
+        for kind in [
+            PlayoutPolicyKind::UniformRandom,
+            PlayoutPolicyKind::GreedyCapture,
+            PlayoutPolicyKind::Softmax,
+        ]
+        .iter()
+        {
+            assert!(playout_with_policy(GameState::default(), kind.policy().as_ref(), rng).is_ok());
+
+            let mut ai = AI::new();
+            ai.set_playout_policy_kind(*kind);
+            for algorithm in [Algorithm::FlatMonteCarlo, Algorithm::MonteCarloTreeSearch].iter() {
+                ai.set_algorithm(*algorithm);
+                let state = GameState::default();
+                let evaluation = ai
+                    .evaluate_state_with_budget(&state, &SearchBudget::playouts(1))
+                    .ok()
+                    .unwrap();
+                assert!(state.is_legal_move(evaluation.recommended_move().unwrap()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_tree_search_from_full_field() {
+        let game = GameState::default(); // beginning of the game
+        let mut search = MonteCarloTreeSearch::new(game.clone(), DEFAULT_EXPLORATION_CONSTANT);
+        search.run(&SearchBudget::playouts(500));
+        let best_move = search.best_move();
+        assert!(best_move.is_some());
+        assert!(game.make_move(best_move.unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_search_budget_limits_playouts_and_time() {
+        let state = GameState::default();
+        let ai = AI::new();
+
+        let evaluation = ai
+            .evaluate_state_with_budget(&state, &SearchBudget::playouts(3))
+            .ok()
+            .unwrap();
+        let num_moves = evaluation.visits().iter().filter(|v| **v > 0).count() as i32;
+        assert_eq!(evaluation.visits().iter().sum::<i32>(), 3 * num_moves);
+
+        let starttime = instant::Instant::now();
+        let evaluation = ai
+            .evaluate_state_with_budget(&state, &SearchBudget::time(50))
+            .ok()
+            .unwrap();
+        assert!(starttime.elapsed().as_millis() < 1000);
+        assert!(evaluation.recommended_move().is_some());
+
+        let starttime = instant::Instant::now();
+        let evaluation = ai
+            .evaluate_state_with_budget(&state, &SearchBudget::playouts_or_time(u32::MAX / 16, 50))
+            .ok()
+            .unwrap();
+        assert!(starttime.elapsed().as_millis() < 1000);
+        assert!(evaluation.recommended_move().is_some());
+
+        let res = game_playout(state, &SearchBudget::playouts_or_time(2, 60 * 1000));
+        assert_eq!(res.unwrap().iter().map(|v| v.total()).sum::<u32>(), 2 * 16);
+
+        let budget = SearchBudget::playouts(u32::MAX).scale_playouts(16);
+        assert_eq!(budget.max_playouts(), Some(u32::MAX));
+        assert_eq!(
+            SearchBudget::playouts(3).scale_playouts(16).max_playouts(),
+            Some(48)
+        );
     }
 
     #[test]
     fn test_full_playout() {
         let game = GameState::default(); // beginning of the game
-        let winner = game_playout(game, Some(1), None);
+        let winner = game_playout(game, &SearchBudget::playouts(1));
         assert!(winner.is_ok());
     }
 
@@ -590,18 +3033,19 @@ mod tests {
     fn test_full_playout_100_times() {
         for _ in 0..100 {
             let game = GameState::default(); // beginning of the game
-            let winner = game_playout(game, Some(1), None);
+            let winner = game_playout(game, &SearchBudget::playouts(1));
             assert!(winner.is_ok());
         }
     }
 
+    #[allow(clippy::single_match)]
     #[bench]
     fn bench_one_move(b: &mut Bencher) {
         b.iter(|| {
             let game = GameState::default(); // beginning of the game
             match game.make_move(0) {
                 Ok(_) => (),
-                Err(_) => ()
+                Err(_) => (),
             }
         });
     }
@@ -612,17 +3056,18 @@ mod tests {
             let n = test::black_box(1);
             for _ in 0..n {
                 let game = GameState::default(); // beginning of the game
-                let _winner = game_playout(game, Some(1), None);
+                let _winner = game_playout(game, &SearchBudget::playouts(1));
             }
         });
     }
 
     #[bench]
     fn create_random_number(b: &mut Bencher) {
+        let mut rng = Rng::new(0);
         b.iter(|| {
             let n = test::black_box(1);
             for _ in 0..n {
-                get_random_number(0, 10);
+                rng.gen_range(0, 10);
             }
         });
     }
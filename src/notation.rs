@@ -0,0 +1,398 @@
+//! Compact text notation of game states (similar to FEN in chess).
+//!
+//! A position is written as
+//! `<cells of player 0>/<cells of player 1> <side to move> [<rules>] [<plies without capture>]`,
+//! e.g. the beginning of the standard game is `2222222222222222/2222222222222222 0 L8`.
+//!
+//! * The cells of every player are given in the internal order (the front row first). Every
+//!   cell is a single character `0`-`9` or `a`-`z` (10 to 35 stones). Larger numbers are written
+//!   in parentheses, e.g. `(40)`.
+//! * The side to move is `0` or `1`.
+//! * The optional rules tag `L<n>` gives the number of cells per row. Without it, the length of
+//!   the field is derived from the number of cells. It may be followed by `R<n>` (number of
+//!   repetitions for a draw) and `C<n>` (maximal number of plies without capture), e.g.
+//!   `L8R3C200`. Omitted draw rules have their default values, 0 disables a rule.
+//! * The optional number of plies since the last capture is 0 if omitted.
+//!
+//! A move is written relative to the player who makes it as `<column><row>`, e.g. `c2`. The
+//! columns `a`, `b`, ... are counted from the left as seen by the player, row `1` is the back row
+//! (next to the player) and row `2` the front row (next to the opponent). A move which captures
+//! stones of the opponent may be followed by the number of captured stones, e.g. `c2x5`.
+
+use crate::setup::MAX_STONES_PER_PLAYER;
+use crate::zobrist;
+use crate::{
+    Direction, GameError, GameState, MoveStatistic, OnePlayersField, RuleSet,
+    DEFAULT_MAX_PLIES_WITHOUT_CAPTURE, DEFAULT_REPETITIONS_FOR_DRAW,
+};
+use std::convert::TryFrom;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// Errors while parsing a position in notation.
+#[derive(Debug, PartialEq, Clone)]
+pub enum NotationError {
+    /// The notation does not consist of 2 to 4 parts separated by whitespace.
+    WrongNumberOfParts(usize),
+    /// The cells of the two players are not separated by a `/`.
+    MissingSeparator,
+    /// A character which does not denote a number of stones.
+    InvalidCell {
+        player: usize,
+        found: char,
+    },
+    /// A number in parentheses which is not closed or not a valid number.
+    InvalidCellNumber {
+        player: usize,
+        found: String,
+    },
+    /// The number of cells of a player does not fit to the rules.
+    WrongNumberOfCells {
+        player: usize,
+        found: usize,
+    },
+    /// A player owns more stones than allowed.
+    TooManyStones {
+        player: usize,
+    },
+    InvalidSideToMove(String),
+    InvalidRuleSet(String),
+    InvalidPliesWithoutCapture(String),
+    /// A move which is not of the form `<column><row>[x<captured stones>]`.
+    InvalidMove(String),
+    /// A move which cannot be made in the position.
+    IllegalMove {
+        notation: String,
+        error: GameError,
+    },
+    /// The number of captured stones given with the move does not fit to the move.
+    WrongCaptureSummary {
+        notation: String,
+        captured: u8,
+    },
+}
+
+impl Display for NotationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            NotationError::WrongNumberOfParts(found) => write!(
+                f,
+                "expected '<cells>/<cells> <side to move> [<rules>] [<plies without capture>]' but found {} parts",
+                found
+            ),
+            NotationError::MissingSeparator => {
+                write!(f, "the cells of the players must be separated by '/'")
+            }
+            NotationError::InvalidCell { player, found } => {
+                write!(f, "invalid cell '{}' of player {}", found, player)
+            }
+            NotationError::InvalidCellNumber { player, found } => {
+                write!(
+                    f,
+                    "invalid number of stones '{}' of player {}",
+                    found, player
+                )
+            }
+            NotationError::WrongNumberOfCells { player, found } => {
+                write!(
+                    f,
+                    "player {} has the wrong number of cells ({})",
+                    player, found
+                )
+            }
+            NotationError::TooManyStones { player } => write!(
+                f,
+                "player {} has more than {} stones",
+                player, MAX_STONES_PER_PLAYER
+            ),
+            NotationError::InvalidSideToMove(found) => {
+                write!(f, "invalid side to move '{}', must be 0 or 1", found)
+            }
+            NotationError::InvalidRuleSet(found) => write!(f, "invalid rules '{}'", found),
+            NotationError::InvalidPliesWithoutCapture(found) => {
+                write!(f, "invalid number of plies without capture '{}'", found)
+            }
+            NotationError::InvalidMove(found) => write!(f, "invalid move '{}'", found),
+            NotationError::IllegalMove { notation, error } => {
+                write!(f, "illegal move '{}' ({:?})", notation, error)
+            }
+            NotationError::WrongCaptureSummary { notation, captured } => write!(
+                f,
+                "move '{}' captures {} stones of the opponent",
+                notation, captured
+            ),
+        }
+    }
+}
+
+impl GameState {
+    /// Parses a position in notation (see the module documentation).
+    pub fn from_notation(notation: &str) -> Result<GameState, NotationError> {
+        let parts: Vec<&str> = notation.split_whitespace().collect();
+        if parts.len() < 2 || parts.len() > 4 {
+            return Err(NotationError::WrongNumberOfParts(parts.len()));
+        }
+
+        let mut fields = parts[0].split('/');
+        let (cells0, cells1) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(cells0), Some(cells1), None) => (cells0, cells1),
+            _ => return Err(NotationError::MissingSeparator),
+        };
+        let cells = [parse_cells(0, cells0)?, parse_cells(1, cells1)?];
+
+        let curr_player = match parts[1] {
+            "0" => 0,
+            "1" => 1,
+            side => return Err(NotationError::InvalidSideToMove(side.to_string())),
+        };
+
+        let mut optional_parts = parts[2..].iter().peekable();
+        let rules_tag =
+            optional_parts.next_if(|part| !part.starts_with(|c: char| c.is_ascii_digit()));
+        let plies_without_capture = match optional_parts.next() {
+            Some(plies) => parse_number::<u32>(plies)
+                .ok_or_else(|| NotationError::InvalidPliesWithoutCapture(plies.to_string()))?,
+            None => 0,
+        };
+        if let Some(part) = optional_parts.next() {
+            return Err(NotationError::InvalidPliesWithoutCapture(part.to_string()));
+        }
+
+        let rules = match rules_tag {
+            Some(tag) => parse_rules(tag)?,
+            None => {
+                RuleSet::new(cells[0].len() / 2).map_err(|_| NotationError::WrongNumberOfCells {
+                    player: 0,
+                    found: cells[0].len(),
+                })?
+            }
+        };
+
+        for (player, player_cells) in cells.iter().enumerate() {
+            if player_cells.len() != rules.number_of_cells() {
+                return Err(NotationError::WrongNumberOfCells {
+                    player,
+                    found: player_cells.len(),
+                });
+            }
+            let stones: u32 = player_cells.iter().map(|stones| *stones as u32).sum();
+            if stones > MAX_STONES_PER_PLAYER {
+                return Err(NotationError::TooManyStones { player });
+            }
+        }
+
+        let [cells0, cells1] = cells;
+        let mut state = GameState {
+            fields_of_players: [
+                OnePlayersField::new(Direction::Left, cells0),
+                OnePlayersField::new(Direction::Right, cells1),
+            ],
+            rules,
+            curr_player,
+            game_over: false,
+            move_statistic: MoveStatistic::default(),
+            plies_without_capture,
+            hash: 0,
+        };
+        state.game_over = state.outcome().is_some();
+        state.hash = zobrist::full_hash(&state);
+        Ok(state)
+    }
+
+    /// Notation of the move of the current player from `position`, including the number of
+    /// captured stones (see the module documentation). Fails if the move is not possible.
+    pub fn move_to_notation(&self, position: usize) -> Result<String, GameError> {
+        if position >= self.rules.number_of_cells() {
+            return Err(GameError::PositionOutsideField);
+        }
+        let new_state = self.make_move(position)?;
+        let (row, col) = self.get_row_col_from_position(self.curr_player as usize, position);
+        let mut notation = format!("{}{}", (b'a' + col as u8) as char, row + 1);
+        let captured = new_state.move_statistic.stones_of_opponent_removed;
+        if captured > 0 {
+            notation.push_str(&format!("x{}", captured));
+        }
+        Ok(notation)
+    }
+
+    /// Position of a move of the current player given in notation. The move has to be possible
+    /// and the number of captured stones, if given, has to match the move.
+    pub fn move_from_notation(&self, notation: &str) -> Result<usize, NotationError> {
+        let invalid = || NotationError::InvalidMove(notation.to_string());
+        let illegal = |error| NotationError::IllegalMove {
+            notation: notation.to_string(),
+            error,
+        };
+
+        let (square, captured) = match notation.find('x') {
+            Some(index) => {
+                let captured = parse_number::<u8>(&notation[index + 1..]).ok_or_else(invalid)?;
+                (&notation[..index], Some(captured))
+            }
+            None => (notation, None),
+        };
+        let mut chars = square.chars();
+        let col = match chars.next() {
+            Some(c @ 'a'..='z') => c as usize - 'a' as usize,
+            _ => return Err(invalid()),
+        };
+        let row = parse_number::<usize>(chars.as_str()).ok_or_else(invalid)?;
+        if !(1..=2).contains(&row) || col >= self.rules.length_of_field {
+            return Err(illegal(GameError::PositionOutsideField));
+        }
+
+        let position = self.get_position_from_row_col(self.curr_player as usize, row - 1, col);
+        if self.outcome().is_some() {
+            return Err(illegal(GameError::NoMoveFound));
+        }
+        let new_state = self.make_move(position).map_err(illegal)?;
+        let actually_captured = new_state.move_statistic.stones_of_opponent_removed;
+        if captured.unwrap_or(actually_captured) != actually_captured {
+            return Err(NotationError::WrongCaptureSummary {
+                notation: notation.to_string(),
+                captured: actually_captured,
+            });
+        }
+        Ok(position)
+    }
+}
+
+#[wasm_bindgen]
+impl GameState {
+    /// Writes the position in notation (see `GameState::from_notation`).
+    pub fn to_notation(&self) -> String {
+        let mut notation = format!(
+            "{}/{} {} {}",
+            format_cells(&self.fields_of_players[0].cells),
+            format_cells(&self.fields_of_players[1].cells),
+            self.curr_player,
+            format_rules(&self.rules)
+        );
+        if self.plies_without_capture > 0 {
+            notation.push_str(&format!(" {}", self.plies_without_capture));
+        }
+        notation
+    }
+
+    /// Parses a position in notation. The error message describes what is wrong.
+    pub fn from_notation_wasm(notation: &str) -> Result<GameState, JsValue> {
+        GameState::from_notation(notation)
+            .map_err(|e| JsValue::from(format!("Invalid position '{}': {}", notation, e).as_str()))
+    }
+
+    /// Notation of the move of the current player from `position`, e.g. `c2x5`.
+    pub fn move_to_notation_wasm(&self, position: usize) -> Result<String, JsValue> {
+        self.move_to_notation(position).map_err(|e| {
+            JsValue::from(format!("Error while writing move {}: {:?}", position, e).as_str())
+        })
+    }
+
+    /// Position of a move of the current player given in notation.
+    pub fn move_from_notation_wasm(&self, notation: &str) -> Result<usize, JsValue> {
+        self.move_from_notation(notation)
+            .map_err(|e| JsValue::from(format!("Invalid move: {}", e).as_str()))
+    }
+}
+
+/// Cells of `player`. A cell with more stones than fit into a cell of the field is rejected as
+/// `TooManyStones`.
+fn parse_cells(player: usize, text: &str) -> Result<Vec<u8>, NotationError> {
+    let mut cells = vec![];
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        let stones = match c {
+            '0'..='9' | 'a'..='z' => c.to_digit(36).unwrap(),
+            '(' => {
+                let mut number = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ')' {
+                        closed = true;
+                        break;
+                    }
+                    number.push(c);
+                }
+                match parse_number::<u32>(&number) {
+                    Some(stones) if closed => stones,
+                    _ => {
+                        return Err(NotationError::InvalidCellNumber {
+                            player,
+                            found: number,
+                        })
+                    }
+                }
+            }
+            _ => return Err(NotationError::InvalidCell { player, found: c }),
+        };
+        let stones = u8::try_from(stones).map_err(|_| NotationError::TooManyStones { player })?;
+        cells.push(stones);
+    }
+    Ok(cells)
+}
+
+/// Number written with ASCII digits only (`str::parse` accepts a leading `+` as well).
+fn parse_number<T: FromStr>(text: &str) -> Option<T> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    text.parse().ok()
+}
+
+fn format_cells(cells: &[u8]) -> String {
+    cells
+        .iter()
+        .map(|stones| match std::char::from_digit(*stones as u32, 36) {
+            Some(c) => c.to_string(),
+            None => format!("({})", stones),
+        })
+        .collect()
+}
+
+/// Rules tag, e.g. `L8`. The draw rules are only given if they differ from the defaults.
+pub(crate) fn format_rules(rules: &RuleSet) -> String {
+    let mut tag = format!("L{}", rules.length_of_field());
+    if rules.repetitions_for_draw() != DEFAULT_REPETITIONS_FOR_DRAW {
+        tag.push_str(&format!("R{}", rules.repetitions_for_draw()));
+    }
+    if rules.max_plies_without_capture() != DEFAULT_MAX_PLIES_WITHOUT_CAPTURE {
+        tag.push_str(&format!("C{}", rules.max_plies_without_capture()));
+    }
+    tag
+}
+
+pub(crate) fn parse_rules(tag: &str) -> Result<RuleSet, NotationError> {
+    let invalid = || NotationError::InvalidRuleSet(tag.to_string());
+    if !tag.starts_with('L') {
+        return Err(invalid());
+    }
+
+    // Split into the rules `L`, `R` and `C` (in this order) and their values
+    let mut values = [None, None, None];
+    let mut rest = tag;
+    for (index, rule) in ['L', 'R', 'C'].iter().enumerate() {
+        if let Some(after_rule) = rest.strip_prefix(*rule) {
+            let end = after_rule
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(after_rule.len());
+            values[index] = Some(after_rule[..end].parse::<u32>().map_err(|_| invalid())?);
+            rest = &after_rule[end..];
+        }
+    }
+    if !rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let length_of_field = values[0].ok_or_else(invalid)? as usize;
+    let mut rules = RuleSet::new(length_of_field).map_err(|_| invalid())?;
+    if let Some(repetitions_for_draw) = values[1] {
+        if repetitions_for_draw > u8::MAX as u32 {
+            return Err(invalid());
+        }
+        rules.set_repetitions_for_draw(repetitions_for_draw as u8);
+    }
+    if let Some(max_plies_without_capture) = values[2] {
+        rules.set_max_plies_without_capture(max_plies_without_capture);
+    }
+    Ok(rules)
+}
@@ -0,0 +1,449 @@
+//! Game records similar to PGN in chess.
+//!
+//! A record starts with tags of the form `[Name "value"]`, one per line, followed by the moves in
+//! move notation (see `crate::notation`) and the result:
+//!
+//! ```text
+//! [Player0 "Alice"]
+//! [Player1 "Bob"]
+//! [Date "2021.03.14"]
+//! [RuleSet "L8"]
+//! [Result "1-0"]
+//!
+//! 1. c2 f2 2. a1x4 h2 ... 1-0
+//! ```
+//!
+//! The tag `Setup` contains the initial position in position notation if the game did not start
+//! from the standard position. The result is `1-0` if player 0 won, `0-1` if player 1 won,
+//! `1/2-1/2` for a draw and `*` if the game is not finished.
+
+use crate::history::PositionHistory;
+use crate::notation::{format_rules, parse_rules, NotationError};
+use crate::session::GameSession;
+use crate::{GameError, GameState, Outcome};
+use std::fmt::{Display, Formatter};
+use wasm_bindgen::prelude::*;
+
+pub const TAG_PLAYER0: &str = "Player0";
+pub const TAG_PLAYER1: &str = "Player1";
+pub const TAG_DATE: &str = "Date";
+pub const TAG_RULE_SET: &str = "RuleSet";
+pub const TAG_SETUP: &str = "Setup";
+pub const TAG_RESULT: &str = "Result";
+
+const RESULT_PLAYER0_WINS: &str = "1-0";
+const RESULT_PLAYER1_WINS: &str = "0-1";
+const RESULT_DRAW: &str = "1/2-1/2";
+const RESULT_UNFINISHED: &str = "*";
+
+/// Maximal length of a line of moves when writing a record.
+const MAX_LINE_LENGTH: usize = 80;
+
+/// Errors while reading a game record.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RecordError {
+    /// A line in the tag section which is not of the form `[Name "value"]`.
+    InvalidTag(String),
+    InvalidRuleSet(String),
+    InvalidSetup(NotationError),
+    /// The tags `RuleSet` and `Setup` do not fit together.
+    RuleSetDoesNotMatchSetup,
+    /// The move at `ply` (the first move is at ply 1) is invalid or illegal.
+    InvalidMove {
+        ply: usize,
+        error: NotationError,
+    },
+    /// A result which is not `1-0`, `0-1`, `1/2-1/2` or `*`.
+    InvalidResult(String),
+    /// The result after the moves differs from the one in the tag `Result`.
+    ResultMismatch {
+        tag: String,
+        moves: String,
+    },
+    /// The game is over, but the record claims a different result.
+    WrongResult {
+        declared: String,
+        actual: String,
+    },
+    /// There are moves after the result.
+    TextAfterResult(String),
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            RecordError::InvalidTag(line) => write!(f, "invalid tag '{}'", line),
+            RecordError::InvalidRuleSet(found) => write!(f, "invalid rules '{}'", found),
+            RecordError::InvalidSetup(e) => write!(f, "invalid setup: {}", e),
+            RecordError::RuleSetDoesNotMatchSetup => {
+                write!(f, "the rules do not fit to the setup")
+            }
+            RecordError::InvalidMove { ply, error } => write!(f, "ply {}: {}", ply, error),
+            RecordError::InvalidResult(found) => write!(f, "invalid result '{}'", found),
+            RecordError::ResultMismatch { tag, moves } => write!(
+                f,
+                "result '{}' of the tag differs from the result '{}' after the moves",
+                tag, moves
+            ),
+            RecordError::WrongResult { declared, actual } => write!(
+                f,
+                "the record claims the result '{}' but the game ended with '{}'",
+                declared, actual
+            ),
+            RecordError::TextAfterResult(found) => {
+                write!(f, "unexpected '{}' after the result", found)
+            }
+        }
+    }
+}
+
+/// A game with its tags, the initial position and all moves.
+#[derive(Clone)]
+pub struct GameRecord {
+    /// Tags in the order in which they are written.
+    tags: Vec<(String, String)>,
+    initial_state: GameState,
+    /// Positions of the moves, every one in the field of the player who made it.
+    moves: Vec<usize>,
+    final_state: GameState,
+    /// All positions from the initial to the final state.
+    history: PositionHistory,
+}
+
+impl GameRecord {
+    /// Record without moves starting from `initial_state`. The tags `Player0`, `Player1` and
+    /// `Date` are unknown (`?`), the result is `*`.
+    pub fn new(initial_state: &GameState) -> GameRecord {
+        let rules = initial_state.rules();
+        let mut record = GameRecord {
+            tags: vec![],
+            initial_state: initial_state.clone(),
+            moves: vec![],
+            final_state: initial_state.clone(),
+            history: PositionHistory::from_states(vec![initial_state]),
+        };
+        record.set_tag(TAG_PLAYER0, "?");
+        record.set_tag(TAG_PLAYER1, "?");
+        record.set_tag(TAG_DATE, "????.??.??");
+        record.set_tag(TAG_RULE_SET, &format_rules(&rules));
+        if !is_standard_start(initial_state) {
+            record.set_tag(TAG_SETUP, &initial_state.to_notation());
+        }
+        record.set_tag(TAG_RESULT, result_of(record.outcome()));
+        record
+    }
+
+    /// Record of the moves of a session up to its current position.
+    pub fn from_session(session: &GameSession) -> GameRecord {
+        let mut record = GameRecord::new(&session.initial_state());
+        for position in session.move_positions() {
+            record
+                .push_move(position)
+                .expect("Moves of a session are legal.");
+        }
+        record
+    }
+
+    /// Value of a tag.
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the value of a tag. New tags are added at the end.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    pub fn initial_state(&self) -> &GameState {
+        &self.initial_state
+    }
+
+    /// State after all moves.
+    pub fn final_state(&self) -> &GameState {
+        &self.final_state
+    }
+
+    pub fn moves(&self) -> &[usize] {
+        &self.moves
+    }
+
+    /// Result of the game after all moves, including draws by repetition.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.history.outcome(&self.final_state)
+    }
+
+    /// Adds the next move. The result is updated if the game is over after the move.
+    pub fn push_move(&mut self, position: usize) -> Result<(), GameError> {
+        if self.outcome().is_some() {
+            return Err(GameError::NoMoveFound);
+        }
+        if position >= self.final_state.rules.number_of_cells() {
+            return Err(GameError::PositionOutsideField);
+        }
+        self.final_state = self.final_state.make_move(position)?;
+        self.history.push(&self.final_state);
+        self.moves.push(position);
+        if self.outcome().is_some() {
+            self.set_tag(TAG_RESULT, result_of(self.outcome()));
+        }
+        Ok(())
+    }
+
+    /// Session with all moves of the record.
+    pub fn to_session(&self) -> GameSession {
+        let mut session = GameSession::new(&self.initial_state);
+        for position in self.moves.iter() {
+            session
+                .make_move(*position)
+                .expect("Moves of a record are legal.");
+        }
+        session
+    }
+
+    /// Reads a record. Every move is replayed and checked.
+    pub fn parse(text: &str) -> Result<GameRecord, RecordError> {
+        let mut tags = vec![];
+        let mut lines = text.lines().map(str::trim).peekable();
+        while let Some(line) = lines.peek() {
+            if line.is_empty() {
+                lines.next();
+            } else if line.starts_with('[') {
+                tags.push(parse_tag(line)?);
+                lines.next();
+            } else {
+                break;
+            }
+        }
+        let tag = |name: &str| {
+            tags.iter()
+                .find(|(tag, _): &&(String, String)| tag == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let rules = match tag(TAG_RULE_SET) {
+            Some(value) => Some(
+                parse_rules(value).map_err(|_| RecordError::InvalidRuleSet(value.to_string()))?,
+            ),
+            None => None,
+        };
+        let initial_state = match tag(TAG_SETUP) {
+            Some(setup) => {
+                let state = GameState::from_notation(setup).map_err(RecordError::InvalidSetup)?;
+                if rules.unwrap_or_else(|| state.rules()) != state.rules() {
+                    return Err(RecordError::RuleSetDoesNotMatchSetup);
+                }
+                state
+            }
+            None => GameState::new(&rules.unwrap_or_default()),
+        };
+
+        let mut record = GameRecord::new(&initial_state);
+        for (name, value) in tags.iter() {
+            record.set_tag(name, value);
+        }
+
+        let mut result = None;
+        for token in lines.flat_map(str::split_whitespace) {
+            if result.is_some() {
+                return Err(RecordError::TextAfterResult(token.to_string()));
+            }
+            if is_result(token) {
+                result = Some(token);
+                continue;
+            }
+            let token = match token.rfind('.') {
+                // skip move numbers like `12.` or `12...`
+                Some(index)
+                    if token[..index]
+                        .chars()
+                        .all(|c| c.is_ascii_digit() || c == '.') =>
+                {
+                    &token[index + 1..]
+                }
+                _ => token,
+            };
+            if token.is_empty() {
+                continue;
+            }
+            if token.contains('-') || token.contains('/') {
+                return Err(RecordError::InvalidResult(token.to_string()));
+            }
+
+            let ply = record.moves.len() + 1;
+            let position = record
+                .final_state
+                .move_from_notation(token)
+                .map_err(|error| RecordError::InvalidMove { ply, error })?;
+            record
+                .push_move(position)
+                .map_err(|error| RecordError::InvalidMove {
+                    ply,
+                    error: NotationError::IllegalMove {
+                        notation: token.to_string(),
+                        error,
+                    },
+                })?;
+        }
+
+        let declared = match (tag(TAG_RESULT), result) {
+            (Some(tag), Some(moves)) if tag != moves => {
+                return Err(RecordError::ResultMismatch {
+                    tag: tag.to_string(),
+                    moves: moves.to_string(),
+                })
+            }
+            (Some(declared), _) | (None, Some(declared)) => declared,
+            (None, None) => RESULT_UNFINISHED,
+        };
+        if !is_result(declared) {
+            return Err(RecordError::InvalidResult(declared.to_string()));
+        }
+        let actual = result_of(record.outcome());
+        if record.outcome().is_some() && declared != actual {
+            return Err(RecordError::WrongResult {
+                declared: declared.to_string(),
+                actual: actual.to_string(),
+            });
+        }
+        record.set_tag(TAG_RESULT, declared);
+        Ok(record)
+    }
+}
+
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        for (name, value) in self.tags.iter() {
+            writeln!(
+                f,
+                "[{} \"{}\"]",
+                name,
+                value.replace('\\', "\\\\").replace('"', "\\\"")
+            )?;
+        }
+        writeln!(f)?;
+
+        let mut tokens = vec![];
+        let mut state = self.initial_state.clone();
+        let first_player = state.curr_player as usize;
+        for (ply, position) in self.moves.iter().enumerate() {
+            let move_number = (ply + first_player) / 2 + 1;
+            if state.curr_player == 0 {
+                tokens.push(format!("{}.", move_number));
+            } else if ply == 0 {
+                tokens.push(format!("{}...", move_number));
+            }
+            tokens.push(
+                state
+                    .move_to_notation(*position)
+                    .expect("Moves of a record are legal."),
+            );
+            state = state
+                .make_move(*position)
+                .expect("Moves of a record are legal.");
+        }
+        tokens.push(
+            self.tag(TAG_RESULT)
+                .unwrap_or(RESULT_UNFINISHED)
+                .to_string(),
+        );
+
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+                writeln!(f)?;
+                line_length = 0;
+            }
+            if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{}", token)?;
+            line_length += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+#[wasm_bindgen]
+impl GameSession {
+    /// Game record of the moves up to the current position.
+    pub fn to_record(&self, player0: &str, player1: &str, date: &str) -> String {
+        let mut record = GameRecord::from_session(self);
+        record.set_tag(TAG_PLAYER0, player0);
+        record.set_tag(TAG_PLAYER1, player1);
+        record.set_tag(TAG_DATE, date);
+        record.to_string()
+    }
+
+    /// Session with all moves of a game record. The error message describes what is wrong.
+    pub fn from_record(text: &str) -> Result<GameSession, JsValue> {
+        GameRecord::parse(text)
+            .map(|record| record.to_session())
+            .map_err(|e| JsValue::from(format!("Invalid game record: {}", e).as_str()))
+    }
+}
+
+fn parse_tag(line: &str) -> Result<(String, String), RecordError> {
+    let invalid = || RecordError::InvalidTag(line.to_string());
+    let content = line
+        .strip_prefix('[')
+        .and_then(|line| line.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (name, value) = content.split_at(content.find(' ').ok_or_else(invalid)?);
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(invalid());
+    }
+
+    let mut unescaped = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.push(chars.next().ok_or_else(invalid)?),
+            '"' => return Err(invalid()),
+            _ => unescaped.push(c),
+        }
+    }
+    Ok((name.to_string(), unescaped))
+}
+
+fn is_result(token: &str) -> bool {
+    [
+        RESULT_PLAYER0_WINS,
+        RESULT_PLAYER1_WINS,
+        RESULT_DRAW,
+        RESULT_UNFINISHED,
+    ]
+    .contains(&token)
+}
+
+pub(crate) fn result_of(outcome: Option<Outcome>) -> &'static str {
+    match outcome {
+        Some(Outcome::Player0Wins) => RESULT_PLAYER0_WINS,
+        Some(Outcome::Player1Wins) => RESULT_PLAYER1_WINS,
+        Some(Outcome::Draw) => RESULT_DRAW,
+        None => RESULT_UNFINISHED,
+    }
+}
+
+/// True if the state is the standard beginning of a game with its rules.
+fn is_standard_start(state: &GameState) -> bool {
+    let start = GameState::new(&state.rules());
+    state.curr_player == start.curr_player
+        && state.fields_of_players[0].cells == start.fields_of_players[0].cells
+        && state.fields_of_players[1].cells == start.fields_of_players[1].cells
+}
@@ -0,0 +1,185 @@
+use crate::history::PositionHistory;
+use crate::{GameError, GameState, MoveStatistic, Outcome};
+use wasm_bindgen::prelude::*;
+
+/// A move which was played in a session.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy)]
+pub struct PlayedMove {
+    /// Player (0 or 1) who made the move.
+    pub player: u8,
+    /// Position on the field of the player from which the move was made.
+    pub position: usize,
+    /// Statistics of the move.
+    pub statistic: MoveStatistic,
+}
+
+/// A game from its initial position together with all moves played so far. Moves can be undone
+/// and redone, and one can jump to any ply. Playing a new move after an undo starts a new
+/// branch, i.e. the undone moves are dropped.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct GameSession {
+    /// All played moves, including the undone moves which can be redone.
+    moves: Vec<PlayedMove>,
+    /// State after every ply, `states[0]` is the initial position. Always one more entry than
+    /// `moves`.
+    states: Vec<GameState>,
+    /// Number of moves which lead to the current position.
+    current_ply: usize,
+}
+
+impl Default for GameSession {
+    fn default() -> Self {
+        GameSession::new(&GameState::default())
+    }
+}
+
+impl GameSession {
+    /// Makes a move for the current player. All undone moves are dropped. Fails if the game is
+    /// over (including a draw by repetition).
+    pub fn make_move(&mut self, position: usize) -> Result<&GameState, GameError> {
+        if self.outcome().is_some() {
+            return Err(GameError::NoMoveFound);
+        }
+        let state = self.current_state_ref();
+        let player = state.curr_player;
+        let newstate = state.make_move(position)?;
+
+        self.moves.truncate(self.current_ply);
+        self.states.truncate(self.current_ply + 1);
+        self.moves.push(PlayedMove {
+            player,
+            position,
+            statistic: newstate.move_statistic,
+        });
+        self.states.push(newstate);
+        self.current_ply += 1;
+
+        Ok(self.current_state_ref())
+    }
+
+    /// Same as `make_move`, but the move is given by row and column relative to the current
+    /// player (see `GameState::make_move_via_row_col`).
+    pub fn make_move_via_row_col(
+        &mut self,
+        row: usize,
+        col: usize,
+    ) -> Result<&GameState, GameError> {
+        let state = self.current_state_ref();
+        state.make_move_via_row_col(row, col)?;
+        let position = state.get_position_from_row_col(state.curr_player as usize, row, col);
+        self.make_move(position)
+    }
+
+    pub fn current_state_ref(&self) -> &GameState {
+        &self.states[self.current_ply]
+    }
+
+    /// All moves of the session, including the undone ones.
+    pub fn moves(&self) -> &[PlayedMove] {
+        &self.moves
+    }
+
+    /// States after every ply up to the current one, the first one is the initial state.
+    pub fn states(&self) -> &[GameState] {
+        &self.states[..=self.current_ply]
+    }
+
+    /// Positions up to the current one.
+    pub fn history(&self) -> PositionHistory {
+        PositionHistory::from_states(self.states())
+    }
+}
+
+#[wasm_bindgen]
+impl GameSession {
+    /// Start a session from the given position.
+    #[wasm_bindgen(constructor)]
+    pub fn new(initial_state: &GameState) -> GameSession {
+        GameSession {
+            moves: vec![],
+            states: vec![initial_state.clone()],
+            current_ply: 0,
+        }
+    }
+
+    pub fn initial_state(&self) -> GameState {
+        self.states[0].clone()
+    }
+
+    pub fn current_state(&self) -> GameState {
+        self.current_state_ref().clone()
+    }
+
+    /// Makes a move for the current player and returns the new state. All undone moves are
+    /// dropped.
+    pub fn make_move_wasm(&mut self, position: usize) -> Result<GameState, JsValue> {
+        match self.make_move(position) {
+            Ok(state) => Ok(state.clone()),
+            Err(e) => Err(JsValue::from(
+                format!("Error while doing a move: {:?}", e).as_str(),
+            )),
+        }
+    }
+
+    /// Result of the game in the current position (`undefined` if the game is not over). Unlike
+    /// `GameState::outcome`, draws by repetition are detected.
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.history().outcome(self.current_state_ref())
+    }
+
+    /// Number of moves which lead to the current position.
+    pub fn current_ply(&self) -> usize {
+        self.current_ply
+    }
+
+    /// Number of moves in the session, including the undone ones.
+    pub fn number_of_plies(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.current_ply > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.current_ply < self.moves.len()
+    }
+
+    /// Takes back the last move. Returns false if there is no move to take back.
+    pub fn undo(&mut self) -> bool {
+        self.can_undo() && self.jump_to_ply(self.current_ply - 1)
+    }
+
+    /// Replays the last undone move. Returns false if there is no move to replay.
+    pub fn redo(&mut self) -> bool {
+        self.jump_to_ply(self.current_ply + 1)
+    }
+
+    /// Goes to the position after `ply` moves (0 is the initial position). Returns false if
+    /// there is no such position.
+    pub fn jump_to_ply(&mut self, ply: usize) -> bool {
+        if ply > self.moves.len() {
+            return false;
+        }
+        self.current_ply = ply;
+        true
+    }
+
+    /// The move played at `ply` (the first move is at ply 1).
+    pub fn move_at(&self, ply: usize) -> Option<PlayedMove> {
+        if ply == 0 {
+            return None;
+        }
+        self.moves.get(ply - 1).copied()
+    }
+
+    /// Positions of all moves up to the current position.
+    pub fn move_positions(&self) -> Vec<usize> {
+        self.moves[..self.current_ply]
+            .iter()
+            .map(|played_move| played_move.position)
+            .collect()
+    }
+}
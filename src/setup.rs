@@ -0,0 +1,105 @@
+use crate::{GameError, RuleSet};
+
+/// Largest number of stones a player may own (the cells hold `u8` values).
+pub const MAX_STONES_PER_PLAYER: u32 = u8::MAX as u32;
+
+/// Distribution of the stones at the beginning of the game.
+///
+/// The cells of a player are given in the internal order, i.e., positions `0..length_of_field`
+/// are the front row (the row next to the opponent) and the remaining positions are the back
+/// row.
+#[derive(Debug, PartialEq, Clone)]
+pub enum StartingLayout {
+    /// Every cell holds the same number of stones. The standard game uses 2 stones per cell.
+    Uniform(u8),
+    /// Only the cells of the front row are filled (traditional setup in which the back row
+    /// starts empty).
+    FrontRow(u8),
+    /// Only the cells of the back row are filled.
+    BackRow(u8),
+    /// Stones arranged by the players themselves before the first move. Each player must place
+    /// exactly `stones_per_player` stones.
+    Arranged {
+        cells: [Vec<u8>; 2],
+        stones_per_player: u32,
+    },
+}
+
+impl Default for StartingLayout {
+    fn default() -> Self {
+        StartingLayout::Uniform(2)
+    }
+}
+
+impl StartingLayout {
+    /// Parse a named layout: `uniform-N`, `front-N` or `back-N` where N is the number of stones
+    /// per filled cell.
+    pub fn from_name(name: &str) -> Result<Self, GameError> {
+        let mut parts = name.trim().splitn(2, '-');
+        let kind = parts.next().unwrap_or("");
+        let stones = parts
+            .next()
+            .and_then(|stones| stones.parse::<u8>().ok())
+            .ok_or(GameError::InvalidSetup)?;
+
+        match kind {
+            "uniform" => Ok(StartingLayout::Uniform(stones)),
+            "front" => Ok(StartingLayout::FrontRow(stones)),
+            "back" => Ok(StartingLayout::BackRow(stones)),
+            _ => Err(GameError::InvalidSetup),
+        }
+    }
+
+    /// Cells of both players for a board with the given rules. Fails if the layout does not fit
+    /// to the board or if the numbers of stones are invalid.
+    pub fn cells(&self, rules: &RuleSet) -> Result<[Vec<u8>; 2], GameError> {
+        let length_of_field = rules.length_of_field();
+        let number_of_cells = rules.number_of_cells();
+
+        let cells = match self {
+            StartingLayout::Uniform(stones) => {
+                let cells = vec![*stones; number_of_cells];
+                [cells.clone(), cells]
+            }
+            StartingLayout::FrontRow(stones) | StartingLayout::BackRow(stones) => {
+                let front = matches!(self, StartingLayout::FrontRow(_));
+                let cells = (0..number_of_cells)
+                    .map(|position| {
+                        if (position < length_of_field) == front {
+                            *stones
+                        } else {
+                            0
+                        }
+                    })
+                    .collect::<Vec<u8>>();
+                [cells.clone(), cells]
+            }
+            StartingLayout::Arranged { cells, .. } => cells.clone(),
+        };
+
+        for player_cells in cells.iter() {
+            if player_cells.len() != number_of_cells {
+                return Err(GameError::InvalidSetup);
+            }
+        }
+
+        let totals = [stones_of(&cells[0]), stones_of(&cells[1])];
+        if totals[0] == 0 || totals[0] != totals[1] || totals[0] > MAX_STONES_PER_PLAYER {
+            return Err(GameError::InvalidSetup);
+        }
+        if let StartingLayout::Arranged {
+            stones_per_player, ..
+        } = self
+        {
+            if totals[0] != *stones_per_player {
+                return Err(GameError::InvalidSetup);
+            }
+        }
+
+        Ok(cells)
+    }
+}
+
+fn stones_of(cells: &[u8]) -> u32 {
+    cells.iter().map(|stones| *stones as u32).sum()
+}
@@ -0,0 +1,177 @@
+//! Matches between two AIs to measure the effect of changes: games with alternating colours,
+//! the Elo difference with its confidence interval and a sequential probability ratio test
+//! (SPRT) which stops the match as soon as the result is clear. `boabin match` plays them.
+//!
+//! All statistics are from the point of view of the first AI of the match.
+
+use crate::ai::AI;
+use crate::record::{GameRecord, TAG_PLAYER0, TAG_PLAYER1};
+use crate::{GameError, GameState, Outcome};
+
+/// Quantile of the normal distribution for a two-sided 95% confidence interval.
+const Z_95: f64 = 1.959_963_985;
+
+/// Wins, draws and losses of the first AI of a match.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    /// Adds the result of a game, `first_is_player0` tells whether the first AI was player 0.
+    pub fn add(&mut self, outcome: Outcome, first_is_player0: bool) {
+        match (outcome, first_is_player0) {
+            (Outcome::Draw, _) => self.draws += 1,
+            (Outcome::Player0Wins, true) | (Outcome::Player1Wins, false) => self.wins += 1,
+            _ => self.losses += 1,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points per game, a draw counts half. None without games.
+    pub fn score(&self) -> Option<f64> {
+        match self.games() {
+            0 => None,
+            games => Some((self.wins as f64 + self.draws as f64 / 2.0) / games as f64),
+        }
+    }
+
+    /// Variance of the points of a single game.
+    fn variance(&self) -> Option<f64> {
+        let score = self.score()?;
+        let games = self.games() as f64;
+        Some(
+            (self.wins as f64 * (1.0 - score).powi(2)
+                + self.draws as f64 * (0.5 - score).powi(2)
+                + self.losses as f64 * score.powi(2))
+                / games,
+        )
+    }
+
+    /// Elo difference between the first and the second AI, infinite if one of them scored
+    /// every point.
+    pub fn elo_difference(&self) -> Option<f64> {
+        self.score().map(elo_from_score)
+    }
+
+    /// Half the width of the 95% confidence interval of the Elo difference.
+    pub fn elo_error(&self) -> Option<f64> {
+        let score = self.score()?;
+        let deviation = (self.variance()? / self.games() as f64).sqrt();
+        let low = elo_from_score((score - Z_95 * deviation).max(0.0));
+        let high = elo_from_score((score + Z_95 * deviation).min(1.0));
+        Some((high - low) / 2.0)
+    }
+}
+
+/// Elo difference of a player who scores `score` points per game.
+pub fn elo_from_score(score: f64) -> f64 {
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Expected points per game of a player who is `elo` stronger.
+pub fn score_from_elo(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Decision of the sequential probability ratio test.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SprtStatus {
+    /// More games are needed.
+    Continue,
+    /// The first AI is not stronger by `elo1` (the hypothesis `elo0` is accepted).
+    AcceptH0,
+    /// The first AI is stronger by `elo1` rather than by `elo0`.
+    AcceptH1,
+}
+
+/// Sequential probability ratio test of the hypothesis that the first AI is `elo0` stronger
+/// than the second against the hypothesis that it is `elo1` stronger. `alpha` and `beta` are the
+/// probabilities of wrongly accepting H1 and H0.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Sprt {
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Sprt {
+            elo0,
+            elo1,
+            alpha: 0.05,
+            beta: 0.05,
+        }
+    }
+
+    /// Bounds of the log-likelihood ratio, H0 is accepted below the lower and H1 above the
+    /// upper bound.
+    pub fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+
+    /// Log-likelihood ratio of the score, approximated by a normal distribution of the points
+    /// per game (GSPRT). 0 as long as all games had the same result.
+    pub fn log_likelihood_ratio(&self, score: &MatchScore) -> f64 {
+        let (points, variance) = match (score.score(), score.variance()) {
+            (Some(points), Some(variance)) if variance > 0.0 => (points, variance),
+            _ => return 0.0,
+        };
+        let score0 = score_from_elo(self.elo0);
+        let score1 = score_from_elo(self.elo1);
+        score.games() as f64 * (score1 - score0) * (2.0 * points - score0 - score1)
+            / (2.0 * variance)
+    }
+
+    pub fn status(&self, score: &MatchScore) -> SprtStatus {
+        let llr = self.log_likelihood_ratio(score);
+        let (lower, upper) = self.bounds();
+        if llr <= lower {
+            SprtStatus::AcceptH0
+        } else if llr >= upper {
+            SprtStatus::AcceptH1
+        } else {
+            SprtStatus::Continue
+        }
+    }
+}
+
+/// Whether the first AI of a match is player 0 in the game with the index `game` (counted from
+/// 0): the colours alternate, starting with the first AI as player 0.
+pub fn first_is_player0(game: u32) -> bool {
+    game.is_multiple_of(2)
+}
+
+/// Plays the game with the index `game` of a match between `ais[0]` and `ais[1]` from
+/// `initial_state` to the end. The names are written to the tags `Player0` and `Player1`.
+pub fn play_match_game(
+    ais: &[AI; 2],
+    names: [&str; 2],
+    game: u32,
+    initial_state: &GameState,
+) -> Result<GameRecord, GameError> {
+    let order = if first_is_player0(game) {
+        [0, 1]
+    } else {
+        [1, 0]
+    };
+    let mut record = GameRecord::new(initial_state);
+    record.set_tag(TAG_PLAYER0, names[order[0]]);
+    record.set_tag(TAG_PLAYER1, names[order[1]]);
+    while record.outcome().is_none() {
+        let state = record.final_state().clone();
+        let position = ais[order[state.curr_player as usize]].next_move(&state)?;
+        record.push_move(position)?;
+    }
+    Ok(record)
+}
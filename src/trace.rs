@@ -0,0 +1,114 @@
+use crate::{GameError, GameState};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+/// Detailed course of a move, e.g. to animate the sowing stone by stone.
+///
+/// All cells are positions on the field of the respective player: `drops` and `pickups` refer
+/// to the field of `player`, `captured_cells` to the field of the opponent.
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MoveTrace {
+    /// Player (0 or 1) who made the move.
+    player: u8,
+    /// Position from which the stones were taken first.
+    start_position: usize,
+    /// Cells into which the stones were dropped, one entry per stone and in the order of the
+    /// sowing.
+    drops: Vec<usize>,
+    /// Cells at which the last stone fell into an occupied cell and all of its stones were
+    /// picked up to continue sowing (relay sowing), in the order of the sowing.
+    pickups: Vec<usize>,
+    /// Cells of the opponent which were emptied by a capture (none or two cells).
+    captured_cells: Vec<usize>,
+    number_of_cells: usize,
+}
+
+impl MoveTrace {
+    pub(crate) fn new(player: u8, start_position: usize, number_of_cells: usize) -> Self {
+        MoveTrace {
+            player,
+            start_position,
+            drops: vec![],
+            pickups: vec![],
+            captured_cells: vec![],
+            number_of_cells,
+        }
+    }
+
+    pub(crate) fn record_drop(&mut self, position: usize) {
+        self.drops.push(position);
+    }
+
+    pub(crate) fn record_pickup(&mut self, position: usize) {
+        self.pickups.push(position);
+    }
+
+    pub(crate) fn record_capture(&mut self, position: usize) {
+        self.captured_cells.push(position);
+    }
+}
+
+#[wasm_bindgen]
+impl MoveTrace {
+    #[wasm_bindgen(getter)]
+    pub fn player(&self) -> u8 {
+        self.player
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn start_position(&self) -> usize {
+        self.start_position
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn drops(&self) -> Vec<usize> {
+        self.drops.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn pickups(&self) -> Vec<usize> {
+        self.pickups.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn captured_cells(&self) -> Vec<usize> {
+        self.captured_cells.clone()
+    }
+
+    /// Cell into which the last stone was dropped.
+    #[wasm_bindgen(getter)]
+    pub fn last_position(&self) -> usize {
+        *self.drops.last().unwrap_or(&self.start_position)
+    }
+
+    /// Number of complete rounds the stones travelled around the two rows of the player (0 for
+    /// a deserialized trace without cells).
+    #[wasm_bindgen(getter)]
+    pub fn laps(&self) -> usize {
+        self.drops
+            .len()
+            .checked_div(self.number_of_cells)
+            .unwrap_or(0)
+    }
+}
+
+impl GameState {
+    /// Makes a move for the current player like `make_move` and records how the stones were
+    /// sown. Slower than `make_move`, so it should not be used by the AI.
+    pub fn make_move_traced(&self, position: usize) -> Result<(GameState, MoveTrace), GameError> {
+        let mut trace = MoveTrace::new(self.curr_player, position, self.rules.number_of_cells());
+        let new_state = self.make_move_with_trace(position, Some(&mut trace))?;
+        Ok((new_state, trace))
+    }
+}
+
+#[wasm_bindgen]
+impl GameState {
+    /// Course of the move of the current player from `position` (the state is not changed).
+    pub fn trace_move(&self, position: usize) -> Result<MoveTrace, JsValue> {
+        self.make_move_traced(position)
+            .map(|(_, trace)| trace)
+            .map_err(|e| JsValue::from(format!("Error while doing a move: {:?}", e).as_str()))
+    }
+}
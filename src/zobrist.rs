@@ -0,0 +1,72 @@
+//! Zobrist hashing of game states.
+//!
+//! The hash of a position is the XOR of one key per occupied cell (depending on the player, the
+//! cell and the number of stones in it), a key for the length of the field and a key for player
+//! 1 to move. A move only changes a few cells, so `GameState::make_move` updates the hash
+//! incrementally instead of hashing all cells again. The number of plies without capture is not
+//! part of the hash.
+//!
+//! A cell may contain up to 255 stones, so the keys are not stored in a table but derived from
+//! the index of the player, cell and number of stones with SplitMix64.
+
+use crate::{GameState, MAX_LENGTH_OF_FIELD};
+
+/// Key which is added when player 1 is to move.
+const SIDE_TO_MOVE_KEY: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Maximal number of cells of a player.
+const MAX_CELLS: u64 = 2 * MAX_LENGTH_OF_FIELD as u64;
+
+/// SplitMix64 mixing function, turns consecutive indices into well distributed keys.
+fn mix(index: u64) -> u64 {
+    let mut z = index.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Key of a cell of `player` which contains `stones` stones. Empty cells have the key 0.
+pub(crate) fn cell_key(player: usize, position: usize, stones: u8) -> u64 {
+    if stones == 0 {
+        return 0;
+    }
+    mix((player as u64 * MAX_CELLS + position as u64) * 256 + stones as u64)
+}
+
+/// Key of the length of the field, so equal cells on fields of different lengths have different
+/// hashes. Its index follows the indices of all cells.
+pub(crate) fn length_key(length_of_field: usize) -> u64 {
+    mix(2 * MAX_CELLS * 256 + length_of_field as u64)
+}
+
+/// Key of the player to move.
+pub(crate) fn side_to_move_key(player: u8) -> u64 {
+    if player == 0 {
+        0
+    } else {
+        SIDE_TO_MOVE_KEY
+    }
+}
+
+/// Hash of a state computed from all of its cells.
+pub(crate) fn full_hash(state: &GameState) -> u64 {
+    let mut hash = length_key(state.rules.length_of_field) ^ side_to_move_key(state.curr_player);
+    for (player, field) in state.fields_of_players.iter().enumerate() {
+        for (position, stones) in field.cells.iter().enumerate() {
+            hash ^= cell_key(player, position, *stones);
+        }
+    }
+    hash
+}
+
+/// Changes `hash` for the cells which differ between `old_cells` and `new_cells` of `player`.
+pub(crate) fn update_cells(hash: u64, player: usize, old_cells: &[u8], new_cells: &[u8]) -> u64 {
+    old_cells
+        .iter()
+        .zip(new_cells.iter())
+        .enumerate()
+        .filter(|(_, (old, new))| old != new)
+        .fold(hash, |hash, (position, (old, new))| {
+            hash ^ cell_key(player, position, *old) ^ cell_key(player, position, *new)
+        })
+}